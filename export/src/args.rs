@@ -4,7 +4,9 @@ use anyhow::Result;
 use clap::Parser;
 use clap_verbosity_flag::Verbosity;
 
-use crate::plot::PlotType;
+use simulation::protocol::ProtocolKind;
+
+use crate::plot::{PlotType, YAxis};
 
 #[derive(Parser, Debug)]
 pub struct Args {
@@ -20,6 +22,15 @@ pub struct Args {
     pub generate_latex: bool,
     #[arg(long, default_value_t = false)]
     pub error_bars: bool,
+    /// Quantity to plot on the y axis
+    #[arg(long, value_enum, default_value_t = YAxis::InteractionCount)]
+    pub y_axis: YAxis,
+    /// Only plot simulations of this protocol
+    ///
+    /// Either `j-majority`, `voter`, `undecided-state`, `q-voter:<q>` or
+    /// `median`
+    #[arg(long)]
+    pub protocol: Option<ProtocolKind>,
 }
 
 fn file_exists(s: &str) -> Result<String> {
@@ -10,6 +10,7 @@ use clap::Parser;
 use common::{CHECKMARK, FACTORY, FOLDER, GRAPH, TOOLS};
 use console::style;
 use indicatif::HumanDuration;
+use itertools::Itertools;
 use pgfplots::Engine;
 
 use args::Args;
@@ -35,8 +36,41 @@ fn main() -> Result<()> {
         console::style("[2/5]").bold().dim(),
         FACTORY
     );
-    let input_file_content = &read_to_string(&args.input)?;
-    let simulations: Vec<Simulation> = serde_json::from_str(input_file_content)?;
+    let simulations = read_simulations(&args.input)?;
+
+    // Censored simulations stopped before reaching consensus. Their
+    // interaction count is only a lower bound, so leave them out of the plots
+    let (simulations, censored): (Vec<_>, Vec<_>) = simulations
+        .into_iter()
+        .partition(|simulation| !simulation.censored);
+    if !censored.is_empty() {
+        println!(
+            "      Skip {} censored simulations",
+            style(censored.len()).bold()
+        );
+    }
+
+    // Runs of different protocols are not comparable, so plot one at a time
+    let simulations = match &args.protocol {
+        Some(protocol) => simulations
+            .into_iter()
+            .filter(|simulation| simulation.protocol.eq(protocol))
+            .collect(),
+        None => {
+            let protocols = simulations
+                .iter()
+                .map(|simulation| simulation.protocol.to_string())
+                .unique()
+                .collect::<Vec<_>>();
+            if protocols.len() > 1 {
+                return Err(anyhow!(
+                    "Input contains the protocols {}, select one via --protocol",
+                    protocols.join(", ")
+                ));
+            }
+            simulations
+        }
+    };
 
     // Generate plot
     println!(
@@ -46,20 +80,15 @@ fn main() -> Result<()> {
     );
     let plot = Plot {
         plot_type: args.plot_type,
+        y_axis: args.y_axis,
         simulations,
     };
-    let (gossip_picture, population_picture) = plot.generate_picture(args.error_bars);
+    let pictures = plot.generate_pictures(args.error_bars);
 
     if args.generate_latex {
-        if let Some(picture) = &population_picture {
+        for (model, picture) in &pictures {
             let mut plot = String::new();
-            let mut file = File::create("population.tex")?;
-            plot.push_str(&picture.standalone_string());
-            file.write_all(plot.as_bytes())?;
-        }
-        if let Some(picture) = &gossip_picture {
-            let mut plot = String::new();
-            let mut file = File::create("gossip.tex")?;
+            let mut file = File::create(format!("{model}.tex"))?;
             plot.push_str(&picture.standalone_string());
             file.write_all(plot.as_bytes())?;
         }
@@ -71,12 +100,7 @@ fn main() -> Result<()> {
         console::style("[4/5]").bold().dim(),
         FOLDER
     );
-    if let Some(picture) = population_picture {
-        picture
-            .show_pdf(Engine::Tectonic)
-            .map_err(|e| anyhow!(e.to_string()))?;
-    }
-    if let Some(picture) = gossip_picture {
+    for (_, picture) in pictures {
         picture
             .show_pdf(Engine::Tectonic)
             .map_err(|e| anyhow!(e.to_string()))?;
@@ -90,3 +114,28 @@ fn main() -> Result<()> {
     );
     Ok(())
 }
+
+/// # Read simulations
+///
+/// Reads simulations stored one per line by the `simulation_runner` or as a
+/// single JSON array in the legacy format. Lines which are not valid JSON,
+/// e.g. the last line of an aborted run, are skipped.
+fn read_simulations(path: &str) -> Result<Vec<Simulation>> {
+    let content = read_to_string(path)?;
+    if content.trim_start().starts_with('[') {
+        return Ok(serde_json::from_str(&content)?);
+    }
+
+    let mut simulations = vec![];
+    let mut skipped = 0;
+    for line in content.lines().filter(|line| !line.trim().is_empty()) {
+        match serde_json::from_str(line) {
+            Ok(simulation) => simulations.push(simulation),
+            Err(_) => skipped += 1,
+        }
+    }
+    if skipped > 0 {
+        println!("      Skip {} incomplete lines", style(skipped).bold());
+    }
+    Ok(simulations)
+}
@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+
+use itertools::Itertools;
+use pgfplots::{
+    axis::{
+        plot::{Plot2D, PlotKey},
+        Axis, AxisKey,
+    },
+    Picture,
+};
+
+use simulation::Simulation;
+
+use crate::util::map_sample_size_to_color;
+
+/// Plots the average number of surviving opinions over the interaction count
+/// for every sample size.
+pub fn generate_extinction_plot(
+    simulations: Vec<Simulation>,
+    _error_bars: bool,
+) -> Option<Picture> {
+    if simulations.len().eq(&0) {
+        return None;
+    }
+
+    // Every simulation starts with k opinions and loses one per extinction
+    let mut initial_opinions = HashMap::new();
+    let mut simulation_counts = HashMap::new();
+    let mut extinction_times = HashMap::new();
+    simulations.iter().for_each(|simulation| {
+        initial_opinions
+            .entry(simulation.j)
+            .and_modify(|v| *v += simulation.k as u64)
+            .or_insert(simulation.k as u64);
+        simulation_counts
+            .entry(simulation.j)
+            .and_modify(|v| *v += 1)
+            .or_insert(1);
+        extinction_times
+            .entry(simulation.j)
+            .or_insert_with(Vec::new)
+            .extend(simulation.extinctions.iter().map(|(_, time)| *time));
+    });
+
+    let mut plots: Vec<pgfplots::axis::plot::Plot> = vec![];
+    let mut entries = vec![];
+    extinction_times
+        .into_iter()
+        .sorted_by(|(first_j, _), (second_j, _)| first_j.cmp(second_j))
+        .for_each(|(j, times)| {
+            let simulation_count = simulation_counts[&j] as f64;
+            let mut surviving = initial_opinions[&j];
+            let mut coordinates = vec![(0.0, surviving as f64 / simulation_count).into()];
+            for (time, group) in &times.into_iter().sorted().group_by(|time| *time) {
+                surviving -= group.count() as u64;
+                coordinates.push((time as f64, surviving as f64 / simulation_count).into());
+            }
+
+            let mut pgf_plot = Plot2D::new();
+            pgf_plot.coordinates = coordinates;
+            pgf_plot.add_key(PlotKey::Custom(String::from("const plot")));
+            pgf_plot.add_key(map_sample_size_to_color(j));
+            plots.push(pgf_plot.into());
+            entries.push(j);
+        });
+
+    let mut axis = Axis::new();
+    axis.set_x_label("Interactions");
+    axis.set_y_label("Surviving opinions");
+    let entries = entries
+        .into_iter()
+        .map(|j| format!("j={}", j))
+        .collect_vec()
+        .join(",");
+    axis.add_key(AxisKey::Custom(format!("legend entries={{{}}}", entries)));
+    axis.add_key(AxisKey::Custom(String::from(
+        "legend style={
+        at={(0.5,1.1)}, % adjust the values to center the legend
+        anchor=south,
+        align=center}",
+    )));
+    axis.add_key(AxisKey::Custom(String::from("legend columns=-1")));
+    axis.add_key(AxisKey::Custom(String::from("ymin=0")));
+    axis.plots = plots;
+    Some(Picture::from(axis))
+}
@@ -11,9 +11,14 @@ use pgfplots::{
 
 use simulation::Simulation;
 
+use super::YAxis;
 use crate::util::{map_sample_size_to_color, map_sample_size_to_markshape};
 
-pub fn generate_j_plot(simulations: Vec<Simulation>, error_bars: bool) -> Option<Picture> {
+pub fn generate_j_plot(
+    simulations: Vec<Simulation>,
+    error_bars: bool,
+    y_axis: &YAxis,
+) -> Option<Picture> {
     if simulations.len().eq(&0) {
         return None;
     }
@@ -24,8 +29,8 @@ pub fn generate_j_plot(simulations: Vec<Simulation>, error_bars: bool) -> Option
     simulations.iter().for_each(|simulation| {
         point_map
             .entry((simulation.j, simulation.k))
-            .and_modify(|v| *v += simulation.interaction_count)
-            .or_insert(simulation.interaction_count);
+            .and_modify(|v| *v += y_axis.value(simulation))
+            .or_insert(y_axis.value(simulation));
         simulation_counts
             .entry((simulation.j, simulation.k))
             .and_modify(|v| *v += 1)
@@ -34,7 +39,7 @@ pub fn generate_j_plot(simulations: Vec<Simulation>, error_bars: bool) -> Option
 
     point_map.iter_mut().for_each(|(k, v)| {
         if let Some(simulation_count) = simulation_counts.get(k) {
-            *v /= simulation_count;
+            *v /= *simulation_count as f64;
         }
     });
 
@@ -64,7 +69,7 @@ pub fn generate_j_plot(simulations: Vec<Simulation>, error_bars: bool) -> Option
             let mut pgf_plot = Plot2D::new();
             pgf_plot.coordinates = points
                 .into_iter()
-                .map(|(j, interaction_count)| (j as f64, interaction_count as f64).into())
+                .map(|(j, interaction_count)| (j as f64, interaction_count).into())
                 .collect_vec();
             pgf_plot.add_key(PlotKey::Marker(Marker::new(
                 map_sample_size_to_markshape(k as u8),
@@ -84,7 +89,7 @@ pub fn generate_j_plot(simulations: Vec<Simulation>, error_bars: bool) -> Option
 
     let mut axis = Axis::new();
     axis.set_x_label("Sample rate");
-    axis.set_y_label("Interactions");
+    axis.set_y_label(y_axis.label());
     let entries = entries
         .into_iter()
         .map(|k| format!("{}", k))
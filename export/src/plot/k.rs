@@ -11,9 +11,14 @@ use pgfplots::{
 
 use simulation::Simulation;
 
+use super::YAxis;
 use crate::util::{map_sample_size_to_color, map_sample_size_to_markshape};
 
-pub fn generate_k_plot(simulations: Vec<Simulation>, error_bars: bool) -> Option<Picture> {
+pub fn generate_k_plot(
+    simulations: Vec<Simulation>,
+    error_bars: bool,
+    y_axis: &YAxis,
+) -> Option<Picture> {
     if simulations.len().eq(&0) {
         return None;
     }
@@ -24,8 +29,8 @@ pub fn generate_k_plot(simulations: Vec<Simulation>, error_bars: bool) -> Option
     simulations.iter().for_each(|simulation| {
         point_map
             .entry((simulation.k, simulation.j))
-            .and_modify(|v| *v += simulation.interaction_count)
-            .or_insert(simulation.interaction_count);
+            .and_modify(|v| *v += y_axis.value(simulation))
+            .or_insert(y_axis.value(simulation));
         simulation_counts
             .entry((simulation.k, simulation.j))
             .and_modify(|v| *v += 1)
@@ -34,7 +39,7 @@ pub fn generate_k_plot(simulations: Vec<Simulation>, error_bars: bool) -> Option
 
     point_map.iter_mut().for_each(|(k, v)| {
         if let Some(simulation_count) = simulation_counts.get(k) {
-            *v /= simulation_count;
+            *v /= *simulation_count as f64;
         }
     });
 
@@ -69,14 +74,14 @@ pub fn generate_k_plot(simulations: Vec<Simulation>, error_bars: bool) -> Option
                     let error_y = if let Some(min) = simulations
                         .iter()
                         .filter(|simulation| simulation.j.eq(&j) && simulation.k.eq(&k))
-                        .map(|simulation| simulation.interaction_count)
-                        .min()
+                        .map(|simulation| y_axis.value(simulation))
+                        .min_by(f64::total_cmp)
                     {
-                        min as f64 / 4.0
+                        min / 4.0
                     } else {
                         0 as f64
                     };
-                    (k as f64, interaction_count as f64, None, Some(error_y)).into()
+                    (k as f64, interaction_count, None, Some(error_y)).into()
                 })
                 .collect_vec();
             pgf_plot.add_key(PlotKey::Marker(Marker::new(
@@ -97,7 +102,7 @@ pub fn generate_k_plot(simulations: Vec<Simulation>, error_bars: bool) -> Option
 
     let mut axis = Axis::new();
     axis.set_x_label("Opinions");
-    axis.set_y_label("Interactions");
+    axis.set_y_label(y_axis.label());
     let entries = entries
         .into_iter()
         .map(|j| format!("{}-Maj.", j))
@@ -1,18 +1,22 @@
 use clap::ValueEnum;
 use pgfplots::Picture;
-use simulation::Simulation;
+use simulation::{Model, Simulation};
 
 use self::{
-    j::generate_j_plot, k::generate_k_plot, n::generate_n_plot, triangle::generate_triangle_plot,
+    extinction::generate_extinction_plot, j::generate_j_plot, k::generate_k_plot,
+    n::generate_n_plot, plurality::generate_plurality_plot, triangle::generate_triangle_plot,
 };
 
+mod extinction;
 mod j;
 mod k;
 mod n;
+mod plurality;
 mod triangle;
 
 pub struct Plot {
     pub plot_type: PlotType,
+    pub y_axis: YAxis,
     pub simulations: Vec<Simulation>,
 }
 
@@ -24,53 +28,76 @@ pub enum PlotType {
     J,
     K,
     N,
+    /// Average number of surviving opinions over time per sample size
+    Extinction,
+    /// Probability that the initial plurality wins over the initial bias
+    Plurality,
     Triangle,
 }
 
+/// Quantity plotted on the y axis.
+#[derive(Clone, Debug, Default, ValueEnum)]
+pub enum YAxis {
+    /// Stored interaction count, rounds in the gossip model and single
+    /// agent updates otherwise
+    #[default]
+    InteractionCount,
+    /// Total number of agent updates
+    Interactions,
+    /// Agent updates divided by the number of agents
+    ParallelTime,
+}
+
+impl YAxis {
+    pub fn value(&self, simulation: &Simulation) -> f64 {
+        match self {
+            YAxis::InteractionCount => simulation.interaction_count as f64,
+            YAxis::Interactions => simulation.agent_updates() as f64,
+            YAxis::ParallelTime => simulation.agent_updates() as f64 / simulation.n as f64,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            YAxis::InteractionCount => "Interactions",
+            YAxis::Interactions => "Agent updates",
+            YAxis::ParallelTime => "Parallel time",
+        }
+    }
+}
+
 pub trait PictureGeneration {
-    fn generate_picture(self, error_bars: bool) -> (Option<Picture>, Option<Picture>);
+    fn generate_pictures(self, error_bars: bool) -> Vec<(Model, Picture)>;
 }
 
 impl PictureGeneration for Plot {
-    fn generate_picture(self, error_bars: bool) -> (Option<Picture>, Option<Picture>) {
-        let mut population_simulations = vec![];
-        let mut gossip_simulations = vec![];
-        self.simulations
-            .into_iter()
-            .for_each(|simulation| match simulation.model {
-                simulation::Model::Gossip => gossip_simulations.push(simulation),
-                simulation::Model::Population => population_simulations.push(simulation),
-            });
+    /// Generates one picture per model present in the simulations.
+    fn generate_pictures(self, error_bars: bool) -> Vec<(Model, Picture)> {
+        let mut pictures = vec![];
+        let mut simulations = self.simulations;
+        for model in [Model::Gossip, Model::Population, Model::Poisson] {
+            let model_simulations;
+            (model_simulations, simulations) = simulations
+                .into_iter()
+                .partition(|simulation| simulation.model == model);
 
-        match self.plot_type {
-            // PlotType::EntropyOverJ => (
-            //     generate_entropy_j_plot(gossip_simulations, error_bars),
-            //     generate_entropy_j_plot(population_simulations, error_bars),
-            // ),
-            // PlotType::EntropyOverK => (
-            //     generate_entropy_k_plot(gossip_simulations, error_bars),
-            //     generate_entropy_k_plot(population_simulations, error_bars),
-            // ),
-            // PlotType::EntropyOverN => (
-            //     generate_entropy_n_plot(gossip_simulations, error_bars),
-            //     generate_entropy_n_plot(population_simulations, error_bars),
-            // ),
-            PlotType::J => (
-                generate_j_plot(gossip_simulations, error_bars),
-                generate_j_plot(population_simulations, error_bars),
-            ),
-            PlotType::K => (
-                generate_k_plot(gossip_simulations, error_bars),
-                generate_k_plot(population_simulations, error_bars),
-            ),
-            PlotType::N => (
-                generate_n_plot(gossip_simulations, error_bars),
-                generate_n_plot(population_simulations, error_bars),
-            ),
-            PlotType::Triangle => (
-                generate_triangle_plot(gossip_simulations, error_bars),
-                generate_triangle_plot(population_simulations, error_bars),
-            ),
+            let picture = match self.plot_type {
+                // PlotType::EntropyOverJ => generate_entropy_j_plot(model_simulations, error_bars),
+                // PlotType::EntropyOverK => generate_entropy_k_plot(model_simulations, error_bars),
+                // PlotType::EntropyOverN => generate_entropy_n_plot(model_simulations, error_bars),
+                PlotType::J => generate_j_plot(model_simulations, error_bars, &self.y_axis),
+                PlotType::K => generate_k_plot(model_simulations, error_bars, &self.y_axis),
+                PlotType::N => generate_n_plot(model_simulations, error_bars, &self.y_axis),
+                PlotType::Extinction => generate_extinction_plot(model_simulations, error_bars),
+                PlotType::Plurality => generate_plurality_plot(model_simulations, error_bars),
+                PlotType::Triangle => {
+                    generate_triangle_plot(model_simulations, error_bars, &self.y_axis)
+                }
+            };
+            if let Some(picture) = picture {
+                pictures.push((model, picture));
+            }
         }
+        pictures
     }
 }
@@ -11,9 +11,14 @@ use pgfplots::{
 
 use simulation::Simulation;
 
+use super::YAxis;
 use crate::util::{map_sample_size_to_color, map_sample_size_to_markshape};
 
-pub fn generate_n_plot(simulations: Vec<Simulation>, _error_bars: bool) -> Option<Picture> {
+pub fn generate_n_plot(
+    simulations: Vec<Simulation>,
+    _error_bars: bool,
+    y_axis: &YAxis,
+) -> Option<Picture> {
     if simulations.len().eq(&0) {
         return None;
     }
@@ -23,8 +28,8 @@ pub fn generate_n_plot(simulations: Vec<Simulation>, _error_bars: bool) -> Optio
     simulations.into_iter().for_each(|simulation| {
         point_map
             .entry((simulation.n, simulation.j))
-            .and_modify(|v| *v += simulation.interaction_count)
-            .or_insert(simulation.interaction_count);
+            .and_modify(|v| *v += y_axis.value(&simulation))
+            .or_insert(y_axis.value(&simulation));
         simulation_counts
             .entry((simulation.n, simulation.j))
             .and_modify(|v| *v += 1)
@@ -33,7 +38,7 @@ pub fn generate_n_plot(simulations: Vec<Simulation>, _error_bars: bool) -> Optio
 
     point_map.iter_mut().for_each(|(k, v)| {
         if let Some(simulation_count) = simulation_counts.get(k) {
-            *v /= simulation_count;
+            *v /= *simulation_count as f64;
         }
     });
 
@@ -63,7 +68,7 @@ pub fn generate_n_plot(simulations: Vec<Simulation>, _error_bars: bool) -> Optio
             let mut pgf_plot = Plot2D::new();
             pgf_plot.coordinates = points
                 .into_iter()
-                .map(|(n, interaction_count)| (n as f64, interaction_count as f64).into())
+                .map(|(n, interaction_count)| (n as f64, interaction_count).into())
                 .collect_vec();
             pgf_plot.add_key(PlotKey::Marker(Marker::new(
                 map_sample_size_to_markshape(j),
@@ -79,7 +84,7 @@ pub fn generate_n_plot(simulations: Vec<Simulation>, _error_bars: bool) -> Optio
 
     let mut axis = Axis::new();
     axis.set_x_label("Number of agents");
-    axis.set_y_label("Interactions");
+    axis.set_y_label(y_axis.label());
     let entries = entries
         .into_iter()
         .map(|j| format!("{}-Maj.", j))
@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+
+use itertools::Itertools;
+use pgfplots::{
+    axis::{
+        plot::{Marker, Plot2D, PlotKey},
+        Axis, AxisKey,
+    },
+    Picture,
+};
+
+use simulation::Simulation;
+
+use crate::util::{map_sample_size_to_color, map_sample_size_to_markshape};
+
+/// Plots the share of simulations in which the initial plurality opinion won
+/// against the initial bias, the difference between the largest and second
+/// largest opinion share of the initial configuration.
+pub fn generate_plurality_plot(simulations: Vec<Simulation>, _error_bars: bool) -> Option<Picture> {
+    // Simulations without unique initial plurality have no winner to check
+    let simulations = simulations
+        .into_iter()
+        .filter(|simulation| simulation.initial_plurality.is_some())
+        .collect_vec();
+    if simulations.len().eq(&0) {
+        return None;
+    }
+
+    let mut win_counts = HashMap::new();
+    let mut simulation_counts = HashMap::new();
+
+    simulations.iter().for_each(|simulation| {
+        let bias = initial_bias(&simulation.config) / simulation.n as f64;
+        // Floats are no valid keys, so group by their bit pattern
+        let key = (simulation.j, bias.to_bits());
+        win_counts
+            .entry(key)
+            .and_modify(|v| *v += simulation.plurality_won as u64)
+            .or_insert(simulation.plurality_won as u64);
+        simulation_counts
+            .entry(key)
+            .and_modify(|v| *v += 1)
+            .or_insert(1);
+    });
+
+    let grouped_points = win_counts
+        .into_iter()
+        .map(|(key, wins)| {
+            let (j, bias) = key;
+            (
+                j,
+                f64::from_bits(bias),
+                wins as f64 / simulation_counts[&key] as f64,
+            )
+        })
+        .sorted_by(|(first_j, _, _), (second_j, _, _)| first_j.cmp(second_j))
+        .group_by(|(j, _, _)| *j)
+        .into_iter()
+        .map(|(j, group)| {
+            (
+                j,
+                group
+                    .map(|(_, bias, probability)| (bias, probability))
+                    .sorted_by(|(first_bias, _), (second_bias, _)| {
+                        first_bias.total_cmp(second_bias)
+                    })
+                    .collect_vec(),
+            )
+        })
+        .collect_vec();
+
+    let mut plots: Vec<pgfplots::axis::plot::Plot> = vec![];
+    let mut entries = vec![];
+    grouped_points.into_iter().for_each(|(j, points)| {
+        let mut pgf_plot = Plot2D::new();
+        pgf_plot.coordinates = points
+            .into_iter()
+            .map(|(bias, probability)| (bias, probability).into())
+            .collect_vec();
+        pgf_plot.add_key(PlotKey::Marker(Marker::new(
+            map_sample_size_to_markshape(j),
+            vec![],
+        )));
+        pgf_plot.add_key(map_sample_size_to_color(j));
+        plots.push(pgf_plot.into());
+        entries.push(j);
+    });
+
+    let mut axis = Axis::new();
+    axis.set_x_label("Initial bias");
+    axis.set_y_label("Plurality win probability");
+    let entries = entries
+        .into_iter()
+        .map(|j| format!("j={}", j))
+        .collect_vec()
+        .join(",");
+    axis.add_key(AxisKey::Custom(format!("legend entries={{{}}}", entries)));
+    axis.add_key(AxisKey::Custom(String::from(
+        "legend style={
+        at={(0.5,1.1)}, % adjust the values to center the legend
+        anchor=south,
+        align=center}",
+    )));
+    axis.add_key(AxisKey::Custom(String::from("legend columns=-1")));
+    axis.add_key(AxisKey::Custom(String::from("ymin=0, ymax=1")));
+    axis.plots = plots;
+    Some(Picture::from(axis))
+}
+
+/// Returns the difference between the largest and the second largest
+/// opinion of an initial configuration.
+fn initial_bias(config: &[u64]) -> f64 {
+    let mut counts = config.to_vec();
+    counts.sort_unstable_by(|first, second| second.cmp(first));
+    (counts.first().unwrap_or(&0) - counts.get(1).unwrap_or(&0)) as f64
+}
@@ -10,9 +10,14 @@ use pgfplots::{
 };
 use simulation::Simulation;
 
+use super::YAxis;
 use crate::util::map_value_to_color;
 
-pub fn generate_triangle_plot(simulations: Vec<Simulation>, _error_bars: bool) -> Option<Picture> {
+pub fn generate_triangle_plot(
+    simulations: Vec<Simulation>,
+    _error_bars: bool,
+    y_axis: &YAxis,
+) -> Option<Picture> {
     if simulations.len().eq(&0) {
         return None;
     }
@@ -23,8 +28,8 @@ pub fn generate_triangle_plot(simulations: Vec<Simulation>, _error_bars: bool) -
     simulations.into_iter().for_each(|simulation| {
         point_map
             .entry(simulation.config.clone())
-            .and_modify(|v| *v += simulation.interaction_count)
-            .or_insert(simulation.interaction_count);
+            .and_modify(|v| *v += y_axis.value(&simulation))
+            .or_insert(y_axis.value(&simulation));
         simulation_counts
             .entry(simulation.config)
             .and_modify(|v| *v += 1)
@@ -33,12 +38,15 @@ pub fn generate_triangle_plot(simulations: Vec<Simulation>, _error_bars: bool) -
 
     point_map.iter_mut().for_each(|(k, v)| {
         if let Some(simulation_count) = simulation_counts.get(k) {
-            *v /= simulation_count;
+            *v /= *simulation_count as f64;
         }
     });
 
-    let lowest_value = *point_map.values().min().unwrap();
-    let highest_value = *point_map.values().max().unwrap();
+    let lowest_value = point_map.values().copied().fold(f64::INFINITY, f64::min);
+    let highest_value = point_map
+        .values()
+        .copied()
+        .fold(f64::NEG_INFINITY, f64::max);
 
     let triangle_points = point_map
         .into_iter()
@@ -1,14 +1,14 @@
 use pgfplots::axis::plot::{MarkShape, PlotKey};
 
-pub fn map_value_to_color(value: u64, lowest_value: u64, highest_value: u64) -> (u8, u8, u8) {
+pub fn map_value_to_color(value: f64, lowest_value: f64, highest_value: f64) -> (u8, u8, u8) {
     // Define the number of color categories
     let num_categories: f64 = 25.0;
 
     // Calculate the value range per category
-    let value_range = (highest_value - lowest_value) as f64 / num_categories;
+    let value_range = (highest_value - lowest_value) / num_categories;
 
     // Calculate the category index based on the value
-    let category_index: f64 = (value - lowest_value) as f64 / value_range;
+    let category_index: f64 = (value - lowest_value) / value_range;
 
     // Calculate the RGB components based on the category index
     let red = (category_index * 255.0) / num_categories;
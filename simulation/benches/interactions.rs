@@ -0,0 +1,84 @@
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use simulation::{
+    adversary::Adversary, config::Config, noise::Noise, protocol::ProtocolKind, rng::RngAlgorithm,
+    tie_break::TieBreak, topology::Topology, Engine, Model, Simulation,
+};
+
+const N: [u64; 2] = [1_000, 100_000];
+const J: [u8; 3] = [1, 3, 7];
+const K: [u16; 2] = [2, 16];
+
+/// Returns the config of a simulation starting from a balanced configuration.
+fn config(model: Model, engine: Engine, n: u64, j: u8, k: u16) -> Config {
+    let mut config = vec![n / k as u64; k as usize];
+    config[0] += n % k as u64;
+    Config {
+        n,
+        j,
+        k,
+        config,
+        config_generator: None,
+        model,
+        engine,
+        parallel_rounds: false,
+        seed: 0,
+        rng: RngAlgorithm::default(),
+        recording: None,
+        metrics: vec![],
+        thresholds: vec![],
+        max_interactions: None,
+        protocol: ProtocolKind::default(),
+        noise: Noise::default(),
+        adversary: Adversary::default(),
+        tie_break: TieBreak::default(),
+        sampling: None,
+        topology: Topology::default(),
+        stubborn: vec![],
+        consensus_fraction: None,
+    }
+}
+
+/// Measures n agent updates per iteration, i.e. one round of the gossip model
+/// or n interactions of the population model. Far from consensus, so no run
+/// stops early.
+fn interactions(c: &mut Criterion) {
+    for (model, engine) in [
+        (Model::Gossip, Engine::Agent),
+        (Model::Gossip, Engine::Count),
+        (Model::Population, Engine::Agent),
+        (Model::Population, Engine::Count),
+    ] {
+        let mut group = c.benchmark_group(format!("{model}/{engine}"));
+        for n in N {
+            for j in J {
+                for k in K {
+                    let mut simulation =
+                        Simulation::new(config(model.clone(), engine.clone(), n, j, k))
+                            .expect("benchmark config should be valid");
+                    // Warm up the buffers reused by all interactions
+                    simulation.step();
+                    let steps = match model {
+                        Model::Gossip => 1,
+                        Model::Population | Model::Poisson => n,
+                    };
+                    group.throughput(Throughput::Elements(n));
+                    group.bench_with_input(
+                        BenchmarkId::from_parameter(format!("n={n}/j={j}/k={k}")),
+                        &simulation,
+                        |b, simulation| {
+                            b.iter_batched(
+                                || simulation.clone(),
+                                |mut simulation| simulation.run_for(steps),
+                                BatchSize::LargeInput,
+                            )
+                        },
+                    );
+                }
+            }
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, interactions);
+criterion_main!(benches);
@@ -0,0 +1,123 @@
+use std::str::FromStr;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{opinion_distribution::OpinionDistribution, tally::Tally};
+
+/// Byzantine agents which never update and report an opinion chosen by a
+/// strategy whenever they are sampled. Disabled by default.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+pub struct Adversary {
+    /// Number of Byzantine agents, part of the n agents
+    pub size: u64,
+    /// Opinion reported by a sampled Byzantine agent
+    pub strategy: Strategy,
+}
+
+/// Strategy choosing the opinion a Byzantine agent reports.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub enum Strategy {
+    /// Report the second most frequent opinion among the honest agents
+    #[default]
+    RunnerUp,
+    /// Always report the same opinion
+    Fixed { opinion: u16 },
+    /// Report the strongest opinion behind the leader of the sample
+    MaximizeTies,
+    /// Report an opinion chosen uniformly at random
+    Random,
+}
+
+impl FromStr for Strategy {
+    type Err = String;
+
+    /// Parses `runner-up`, `fixed:<opinion>`, `maximize-ties` or `random`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            None if s == "runner-up" => Ok(Strategy::RunnerUp),
+            None if s == "maximize-ties" => Ok(Strategy::MaximizeTies),
+            None if s == "random" => Ok(Strategy::Random),
+            Some(("fixed", opinion)) => Ok(Strategy::Fixed {
+                opinion: opinion
+                    .parse()
+                    .map_err(|_| String::from("invalid opinion for strategy fixed"))?,
+            }),
+            _ => Err(String::from(
+                "expected `runner-up`, `fixed:<opinion>`, `maximize-ties` or `random`",
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for Strategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Strategy::RunnerUp => write!(f, "runner-up"),
+            Strategy::Fixed { opinion } => write!(f, "fixed:{opinion}"),
+            Strategy::MaximizeTies => write!(f, "maximize-ties"),
+            Strategy::Random => write!(f, "random"),
+        }
+    }
+}
+
+impl Adversary {
+    /// Adds the reports of `byzantine` sampled Byzantine agents to a sample
+    /// given as opinion counts of the sampled honest agents.
+    pub fn corrupt<R: Rng + ?Sized>(
+        &self,
+        sample: &mut Tally,
+        byzantine: u64,
+        opinion_distribution: &OpinionDistribution,
+        k: u16,
+        rng: &mut R,
+    ) {
+        for _ in 0..byzantine {
+            let opinion = self.strategy.report(sample, opinion_distribution, k, rng);
+            sample.add(opinion);
+        }
+    }
+}
+
+impl Strategy {
+    /// Returns the opinion reported into the given sample.
+    pub fn report<R: Rng + ?Sized>(
+        &self,
+        sample: &Tally,
+        opinion_distribution: &OpinionDistribution,
+        k: u16,
+        rng: &mut R,
+    ) -> u16 {
+        match self {
+            Strategy::RunnerUp => {
+                // Opinions are ranked by count, ties by the smaller id
+                let rank = |opinion: &u16| {
+                    (
+                        std::cmp::Reverse(opinion_distribution.get(*opinion)),
+                        *opinion,
+                    )
+                };
+                let leader = (0..k).min_by_key(rank).unwrap_or(0);
+                (0..k)
+                    .filter(|opinion| *opinion != leader)
+                    .min_by_key(rank)
+                    .unwrap_or(leader)
+            }
+            Strategy::Fixed { opinion } => *opinion,
+            Strategy::MaximizeTies => {
+                // Strengthen the largest opinion behind the leader, so that
+                // the leader is tied or its lead shrinks
+                let count = |opinion: u16| sample.get(opinion);
+                let max_count = (0..k).map(count).max().unwrap_or(0);
+                let behind = (0..k).filter(|opinion| count(*opinion) < max_count);
+                match behind.max_by_key(|opinion| (count(*opinion), std::cmp::Reverse(*opinion))) {
+                    Some(opinion) => opinion,
+                    // All opinions are tied, any report breaks the tie
+                    None => rng.gen_range(0..k),
+                }
+            }
+            Strategy::Random => rng.gen_range(0..k),
+        }
+    }
+}
@@ -1,37 +1,105 @@
 #[derive(Clone, Debug)]
 pub struct Agent {
     pub opinion: u16,
+    /// Stubborn agents are sampled by others but never change their opinion
+    pub stubborn: bool,
 }
 
-use rand::seq::SliceRandom;
-use std::collections::HashMap;
+use rand::Rng;
 
-use crate::opinion_distribution::OpinionDistribution;
+use crate::{
+    noise::Noise, opinion_distribution::OpinionDistribution, protocol::Protocol, tally::Tally,
+    tie_break::TieBreak,
+};
 
 impl Agent {
     pub fn new(opinion: u16) -> Self {
-        Agent { opinion }
+        Agent {
+            opinion,
+            stubborn: false,
+        }
+    }
+
+    pub fn stubborn(opinion: u16) -> Self {
+        Agent {
+            opinion,
+            stubborn: true,
+        }
     }
 
-    /// Executes the interaction for an agent and a given sample and updates the simulations
-    /// interaction count. Returns the updated opinion as an option.
-    pub fn update(&mut self, sample: Vec<&Agent>, opinion_distribution: &mut OpinionDistribution) {
-        // Counts the occurence of each opinion and find the major opinion.
-        let mut counts = HashMap::new();
-        sample.iter().for_each(|agent| {
-            *counts.entry(agent.opinion).or_insert(0) += 1;
+    /// Executes the interaction for an agent and a given sample, given as
+    /// number of sampled agents per opinion, according to the protocol and
+    /// updates the opinion distribution. With the noise probability the
+    /// sample is ignored.
+    #[allow(clippy::too_many_arguments)]
+    pub fn update<R: Rng>(
+        &mut self,
+        sample: &Tally,
+        opinion_distribution: &mut OpinionDistribution,
+        protocol: &dyn Protocol,
+        noise: &Noise,
+        k: u16,
+        tie_break: &TieBreak,
+        rng: &mut R,
+    ) {
+        if self.stubborn {
+            return;
+        }
+        let own_opinion = self.opinion;
+        let new_opinion = noise.apply(k, rng, |rng| {
+            protocol.update(sample, own_opinion, k, tie_break, rng)
         });
-        let max_count = counts.values().max().unwrap_or(&0);
-        let major_opinions: Vec<u16> = counts
-            .iter()
-            .filter(|&(_, &count)| count == *max_count)
-            .map(|(&elem, _)| elem)
-            .collect();
-
-        // On a tie, choose arbitrarily and update.
-        if let Some(major_opinion) = major_opinions.choose(&mut rand::thread_rng()) {
-            opinion_distribution.update(Some(self.opinion), *major_opinion);
-            self.opinion = *major_opinion;
+        if let Some(new_opinion) = new_opinion {
+            opinion_distribution.update(Some(self.opinion), new_opinion);
+            self.opinion = new_opinion;
+        }
+    }
+}
+
+/// Returns the most frequent opinion of a sample given as opinion counts.
+/// On a tie, the new opinion is chosen according to the tie-break rule.
+pub fn choose_major_opinion<R: Rng + ?Sized>(
+    sample: &Tally,
+    own_opinion: u16,
+    tie_break: &TieBreak,
+    rng: &mut R,
+) -> Option<u16> {
+    // With self-vote the own opinion counts as an additional vote
+    let self_vote = tie_break.eq(&TieBreak::SelfVote);
+    let votes = sample.iter_with(own_opinion, self_vote as u64);
+    let max_count = votes.clone().map(|(_, count)| count).max().unwrap_or(0);
+    let mut major_opinions = votes
+        .filter(|(_, count)| *count == max_count)
+        .map(|(opinion, _)| opinion);
+
+    let ties = major_opinions.clone().count();
+    if ties > 1 {
+        match tie_break {
+            TieBreak::KeepOwn => return Some(own_opinion),
+            TieBreak::SmallestOpinion => return major_opinions.next(),
+            TieBreak::RandomSampleMember => return choose_sample_member(sample, rng),
+            TieBreak::Uniform | TieBreak::SelfVote => {}
+        }
+    }
+    if ties == 0 {
+        return None;
+    }
+    // Same draw as choosing from a slice of the major opinions
+    major_opinions.nth(rng.gen_range(0..ties as u32) as usize)
+}
+
+/// Returns the opinion of a uniformly chosen member of a sample given as
+/// opinion counts.
+pub fn choose_sample_member<R: Rng + ?Sized>(sample: &Tally, rng: &mut R) -> Option<u16> {
+    if sample.total() == 0 {
+        return None;
+    }
+    let mut index = rng.gen_range(0..sample.total());
+    for (opinion, count) in sample.iter() {
+        if index < count {
+            return Some(opinion);
         }
+        index -= count;
     }
+    None
 }
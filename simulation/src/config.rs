@@ -1,6 +1,24 @@
-use crate::Model;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug)]
+use crate::{
+    adversary::{Adversary, Strategy},
+    error::SimulationError,
+    generator::ConfigGenerator,
+    noise::Noise,
+    observer::Metric,
+    protocol::ProtocolKind,
+    recorder::Cadence,
+    rng::RngAlgorithm,
+    sampling::Sampling,
+    tie_break::TieBreak,
+    topology::Topology,
+    Engine, Model,
+};
+
+/// Parameters of a simulation. Can be loaded from a scenario file, where all
+/// fields but n, j, k, config and model are optional.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
 pub struct Config {
     /// Number of agents
     pub n: u64,
@@ -8,8 +26,178 @@ pub struct Config {
     pub j: u8,
     /// Number of opinions
     pub k: u16,
-    /// Initial consensus configuration
+    /// Initial consensus configuration of the honest agents
     pub config: Vec<u64>,
+    /// Generator which produced the initial configuration, if any
+    #[serde(default)]
+    pub config_generator: Option<ConfigGenerator>,
     /// Process model defining the interaction behavior
     pub model: Model,
+    /// Engine storing the state of the agents
+    #[serde(default)]
+    pub engine: Engine,
+    /// Compute the rounds of the gossip model on all cores
+    #[serde(default)]
+    pub parallel_rounds: bool,
+    /// Seed of the random number generator, chosen randomly if not set
+    #[serde(default = "rand::random")]
+    pub seed: u64,
+    /// Algorithm of the random number generator
+    #[serde(default)]
+    pub rng: RngAlgorithm,
+    /// Cadence of opinion distribution snapshots, disabled if not set
+    #[serde(default)]
+    pub recording: Option<Cadence>,
+    /// Metrics calculated by built-in observers once per round
+    #[serde(default)]
+    pub metrics: Vec<Metric>,
+    /// Opinion shares whose hitting times are recorded together with the
+    /// times at which opinions die out, disabled if empty
+    #[serde(default)]
+    pub thresholds: Vec<f64>,
+    /// Maximum number of interactions before a simulation is stopped
+    #[serde(default)]
+    pub max_interactions: Option<u64>,
+    /// Update rule of the agents
+    #[serde(default)]
+    pub protocol: ProtocolKind,
+    /// Noise perturbing the updates of the agents
+    #[serde(default)]
+    pub noise: Noise,
+    /// Byzantine agents corrupting the samples of honest agents
+    #[serde(default)]
+    pub adversary: Adversary,
+    /// Rule deciding the new opinion on a tie
+    #[serde(default)]
+    pub tie_break: TieBreak,
+    /// Defines how agents draw their sample. If not set, the default of the
+    /// model is used.
+    #[serde(default)]
+    pub sampling: Option<Sampling>,
+    /// Graph on which the agents interact
+    #[serde(default)]
+    pub topology: Topology,
+    /// Number of stubborn agents per opinion, part of the initial
+    /// configuration
+    #[serde(default)]
+    pub stubborn: Vec<u64>,
+    /// Share of agents which have to agree on an opinion to reach consensus.
+    /// If not set, all non-stubborn agents have to agree.
+    #[serde(default)]
+    pub consensus_fraction: Option<f64>,
+}
+
+impl Config {
+    /// Returns the sampling of the config or the default of its model.
+    pub fn sampling(&self) -> Sampling {
+        self.sampling
+            .unwrap_or_else(|| Sampling::default_for(&self.model))
+    }
+
+    /// Checks whether a simulation can be built from this config and returns
+    /// the first problem found otherwise.
+    pub fn validate(&self) -> Result<(), SimulationError> {
+        if self.adversary.size >= self.n {
+            return Err(SimulationError::AdversarySize {
+                size: self.adversary.size,
+                n: self.n,
+            });
+        }
+        if let Strategy::Fixed { opinion } = self.adversary.strategy {
+            if opinion >= self.k {
+                return Err(SimulationError::FixedOpinion { opinion, k: self.k });
+            }
+        }
+        if let Some(generator) = &self.config_generator {
+            generator.validate()?;
+        }
+        if self.config.len() != self.k as usize {
+            return Err(SimulationError::ConfigLength {
+                k: self.k,
+                actual: self.config.len(),
+            });
+        }
+        let honest_agents = self.n - self.adversary.size;
+        let configured_agents = self.config.iter().sum::<u64>();
+        if configured_agents != honest_agents {
+            return Err(SimulationError::ConfigSum {
+                expected: honest_agents,
+                actual: configured_agents,
+            });
+        }
+        self.topology.validate(self.n)?;
+        // Without replacement an agent samples distinct agents out of the
+        // agents it may sample, on a graph out of its neighbors
+        let sampling = self.sampling();
+        let max_sample_size = if sampling.with_replacement {
+            u8::MAX as u64
+        } else {
+            let neighbors = self.topology.degree().unwrap_or(self.n - 1);
+            neighbors + sampling.include_self as u64
+        };
+        if self.j == 0 || self.j as u64 > max_sample_size {
+            return Err(SimulationError::SampleSize {
+                j: self.j,
+                max: max_sample_size,
+            });
+        }
+        // Otherwise no sample ever convinces an agent
+        if let ProtocolKind::QVoter { q } = self.protocol {
+            if q > self.j {
+                return Err(SimulationError::QVoterThreshold { q, j: self.j });
+            }
+        }
+        if self.engine == Engine::Count && self.topology != Topology::Complete {
+            return Err(SimulationError::CountEngineTopology);
+        }
+        if self.parallel_rounds && (self.model != Model::Gossip || self.engine != Engine::Agent) {
+            return Err(SimulationError::ParallelRounds);
+        }
+        if !self.stubborn.is_empty() && self.stubborn.len() != self.k as usize {
+            return Err(SimulationError::StubbornLength {
+                k: self.k,
+                actual: self.stubborn.len(),
+            });
+        }
+        if self
+            .stubborn
+            .iter()
+            .zip(&self.config)
+            .any(|(stubborn, agents)| stubborn > agents)
+        {
+            return Err(SimulationError::StubbornAgents);
+        }
+        if let Some(fraction) = self.consensus_fraction {
+            if !(fraction > 0.0 && fraction <= 1.0) {
+                return Err(SimulationError::ConsensusFraction(fraction));
+            }
+        }
+        if !(0.0..=1.0).contains(&self.noise.probability) {
+            return Err(SimulationError::NoiseProbability(self.noise.probability));
+        }
+        // Under noise exact consensus may never be reached
+        if self.noise.is_enabled()
+            && self.consensus_fraction.is_none()
+            && self.max_interactions.is_none()
+        {
+            return Err(SimulationError::NoiseWithoutStop);
+        }
+        if self.topology.may_be_disconnected()
+            && self.consensus_fraction.is_none()
+            && self.max_interactions.is_none()
+        {
+            return Err(SimulationError::TopologyWithoutStop);
+        }
+        if let Some(cadence) = &self.recording {
+            cadence.validate()?;
+        }
+        if let Some(threshold) = self
+            .thresholds
+            .iter()
+            .find(|threshold| !(**threshold > 0.0 && **threshold <= 1.0))
+        {
+            return Err(SimulationError::HittingTimeThreshold(*threshold));
+        }
+        Ok(())
+    }
 }
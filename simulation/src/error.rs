@@ -2,6 +2,71 @@ use thiserror::Error;
 
 #[derive(Debug, Error)]
 pub enum SimulationError {
-    #[error("Bad configuration")]
-    BadConfig(#[from] rand::distributions::WeightedError),
+    #[error("Initial configuration has {actual} entries instead of k = {k}")]
+    ConfigLength { k: u16, actual: usize },
+    #[error("Initial configuration sums up to {actual} instead of the {expected} honest agents")]
+    ConfigSum { expected: u64, actual: u64 },
+    #[error("Sample size {j} has to be between 1 and {max}")]
+    SampleSize { j: u8, max: u64 },
+    #[error("Invalid topology: {0}")]
+    InvalidTopology(String),
+    #[error("Random topologies require a consensus fraction or a maximum number of interactions")]
+    TopologyWithoutStop,
+    #[error("The count engine only supports the complete topology")]
+    CountEngineTopology,
+    #[error("Parallel rounds require the gossip model and the agent engine")]
+    ParallelRounds,
+    #[error("Threshold q = {q} of the q-voter protocol exceeds the sample size j = {j}")]
+    QVoterThreshold { q: u8, j: u8 },
+    #[error("Stubborn configuration has {actual} entries instead of k = {k}")]
+    StubbornLength { k: u16, actual: usize },
+    #[error("More stubborn agents than agents of an opinion")]
+    StubbornAgents,
+    #[error("Consensus fraction {0} is not in (0, 1]")]
+    ConsensusFraction(f64),
+    #[error("Noise probability {0} is not in [0, 1]")]
+    NoiseProbability(f64),
+    #[error("Noise requires a consensus fraction or a maximum number of interactions")]
+    NoiseWithoutStop,
+    #[error("Adversary size {size} leaves no honest agent out of {n} agents")]
+    AdversarySize { size: u64, n: u64 },
+    #[error("Fixed opinion {opinion} of the adversary is not one of the k = {k} opinions")]
+    FixedOpinion { opinion: u16, k: u16 },
+    #[error("Invalid recording cadence: {0}")]
+    InvalidCadence(String),
+    #[error("Hitting time threshold {0} is not in (0, 1]")]
+    HittingTimeThreshold(f64),
+    #[error("Invalid initial configuration generator: {0}")]
+    InvalidGenerator(String),
+    #[error("Could not read initial configurations")]
+    ConfigFile(#[source] std::io::Error),
+    #[error("Could not read edge list")]
+    EdgeList(#[from] std::io::Error),
+}
+
+impl SimulationError {
+    /// Returns the name of the config field causing the error.
+    pub fn field(&self) -> &'static str {
+        match self {
+            SimulationError::ConfigLength { .. } | SimulationError::ConfigSum { .. } => "config",
+            SimulationError::SampleSize { .. } => "j",
+            SimulationError::InvalidTopology(_)
+            | SimulationError::TopologyWithoutStop
+            | SimulationError::EdgeList(_) => "topology",
+            SimulationError::CountEngineTopology => "engine",
+            SimulationError::ParallelRounds => "parallel_rounds",
+            SimulationError::QVoterThreshold { .. } => "protocol",
+            SimulationError::StubbornLength { .. } | SimulationError::StubbornAgents => "stubborn",
+            SimulationError::ConsensusFraction(_) => "consensus_fraction",
+            SimulationError::NoiseProbability(_) | SimulationError::NoiseWithoutStop => "noise",
+            SimulationError::AdversarySize { .. } | SimulationError::FixedOpinion { .. } => {
+                "adversary"
+            }
+            SimulationError::InvalidCadence(_) => "recording",
+            SimulationError::HittingTimeThreshold(_) => "thresholds",
+            SimulationError::InvalidGenerator(_) | SimulationError::ConfigFile(_) => {
+                "config_generator"
+            }
+        }
+    }
 }
@@ -0,0 +1,240 @@
+use std::fs::read_to_string;
+use std::str::FromStr;
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{error::SimulationError, rng::derive_seed};
+
+/// Generator of initial configurations. Opinion 0 is the favored opinion of
+/// biased configurations.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub enum ConfigGenerator {
+    /// All configurations whose supports are multiples of `step`, except for
+    /// the last opinion which holds the remaining agents
+    Lattice { step: u64 },
+    /// Opinion 0 holds `bias` more agents than each other opinion
+    AdditiveBias { bias: u64 },
+    /// Opinion 0 holds `factor` times the agents of each other opinion
+    MultiplicativeBias { factor: f64 },
+    /// Support of opinion `i` proportional to `1 / (i + 1)^exponent`
+    Zipf { exponent: f64 },
+    /// Supports drawn from a symmetric Dirichlet distribution with
+    /// concentration `alpha`
+    Dirichlet { alpha: f64 },
+    /// Configurations loaded from a file with one comma separated
+    /// configuration per line
+    Csv { path: String },
+}
+
+impl FromStr for ConfigGenerator {
+    type Err = String;
+
+    /// Parses `lattice:<step>`, `additive-bias:<bias>`,
+    /// `multiplicative-bias:<factor>`, `zipf:<exponent>`, `dirichlet:<alpha>`
+    /// or `csv:<path>`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, parameter) = s.split_once(':').unwrap_or((s, ""));
+        let parse_error = |parameter: &str| format!("invalid {parameter} for generator {kind}");
+        match kind {
+            "lattice" => Ok(ConfigGenerator::Lattice {
+                step: parameter.parse().map_err(|_| parse_error("step"))?,
+            }),
+            "additive-bias" => Ok(ConfigGenerator::AdditiveBias {
+                bias: parameter.parse().map_err(|_| parse_error("bias"))?,
+            }),
+            "multiplicative-bias" => Ok(ConfigGenerator::MultiplicativeBias {
+                factor: parameter.parse().map_err(|_| parse_error("factor"))?,
+            }),
+            "zipf" => Ok(ConfigGenerator::Zipf {
+                exponent: parameter.parse().map_err(|_| parse_error("exponent"))?,
+            }),
+            "dirichlet" => Ok(ConfigGenerator::Dirichlet {
+                alpha: parameter.parse().map_err(|_| parse_error("alpha"))?,
+            }),
+            "csv" if !parameter.is_empty() => Ok(ConfigGenerator::Csv {
+                path: parameter.to_string(),
+            }),
+            _ => Err(String::from(
+                "expected `lattice:<step>`, `additive-bias:<bias>`, \
+                `multiplicative-bias:<factor>`, `zipf:<exponent>`, \
+                `dirichlet:<alpha>` or `csv:<path>`",
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for ConfigGenerator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigGenerator::Lattice { step } => write!(f, "lattice:{step}"),
+            ConfigGenerator::AdditiveBias { bias } => write!(f, "additive-bias:{bias}"),
+            ConfigGenerator::MultiplicativeBias { factor } => {
+                write!(f, "multiplicative-bias:{factor}")
+            }
+            ConfigGenerator::Zipf { exponent } => write!(f, "zipf:{exponent}"),
+            ConfigGenerator::Dirichlet { alpha } => write!(f, "dirichlet:{alpha}"),
+            ConfigGenerator::Csv { path } => write!(f, "csv:{path}"),
+        }
+    }
+}
+
+impl ConfigGenerator {
+    /// Checks the parameters of the generator.
+    pub fn validate(&self) -> Result<(), SimulationError> {
+        let invalid = |message: &str| Err(SimulationError::InvalidGenerator(message.to_string()));
+        match self {
+            ConfigGenerator::Lattice { step: 0 } => invalid("lattice step has to be positive"),
+            ConfigGenerator::MultiplicativeBias { factor }
+                if !(factor.is_finite() && *factor > 0.0) =>
+            {
+                invalid("bias factor has to be positive")
+            }
+            ConfigGenerator::Zipf { exponent } if !(exponent.is_finite() && *exponent >= 0.0) => {
+                invalid("zipf exponent has to be non-negative")
+            }
+            ConfigGenerator::Dirichlet { alpha } if !(alpha.is_finite() && *alpha > 0.0) => {
+                invalid("dirichlet concentration has to be positive")
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Generates initial configurations of `n` agents with `k` opinions.
+    /// Random configurations are drawn reproducibly from `seed`.
+    pub fn generate(&self, n: u64, k: u16, seed: u64) -> Result<Vec<Vec<u64>>, SimulationError> {
+        self.validate()?;
+        let k = k as usize;
+        let configs = match self {
+            ConfigGenerator::Lattice { step } => {
+                let mut configs = vec![];
+                lattice(n, k, *step, &mut vec![], &mut configs);
+                configs
+            }
+            ConfigGenerator::AdditiveBias { bias } => {
+                let mut config = apportion(n.saturating_sub(*bias), &vec![1.0; k]);
+                if let Some(favored) = config.first_mut() {
+                    *favored += bias.min(&n);
+                }
+                vec![config]
+            }
+            ConfigGenerator::MultiplicativeBias { factor } => {
+                let mut weights = vec![1.0; k];
+                if let Some(favored) = weights.first_mut() {
+                    *favored = *factor;
+                }
+                vec![apportion(n, &weights)]
+            }
+            ConfigGenerator::Zipf { exponent } => {
+                let weights = (0..k)
+                    .map(|opinion| 1.0 / ((opinion + 1) as f64).powf(*exponent))
+                    .collect::<Vec<_>>();
+                vec![apportion(n, &weights)]
+            }
+            ConfigGenerator::Dirichlet { alpha } => {
+                // Separate stream from the runs of the simulation
+                let mut rng = ChaCha8Rng::seed_from_u64(derive_seed(seed, u64::MAX));
+                let weights = (0..k).map(|_| gamma(*alpha, &mut rng)).collect::<Vec<_>>();
+                vec![apportion(n, &weights)]
+            }
+            ConfigGenerator::Csv { path } => {
+                let content = read_to_string(path).map_err(SimulationError::ConfigFile)?;
+                content
+                    .lines()
+                    .filter(|line| !line.trim().is_empty())
+                    .map(|line| {
+                        line.split(',')
+                            .map(|support| support.trim().parse())
+                            .collect::<Result<Vec<u64>, _>>()
+                            .map_err(|_| {
+                                SimulationError::InvalidGenerator(format!(
+                                    "invalid configuration `{line}` in {path}"
+                                ))
+                            })
+                    })
+                    .collect::<Result<_, _>>()?
+            }
+        };
+        Ok(configs)
+    }
+}
+
+/// Enumerates all configurations starting with `prefix` whose supports are
+/// multiples of `step`, except for the last one.
+fn lattice(
+    remaining: u64,
+    k: usize,
+    step: u64,
+    prefix: &mut Vec<u64>,
+    configs: &mut Vec<Vec<u64>>,
+) {
+    if prefix.len() + 1 >= k {
+        let mut config = prefix.clone();
+        config.resize(k, remaining);
+        configs.push(config);
+        return;
+    }
+    let mut support = 0;
+    while support <= remaining {
+        prefix.push(support);
+        lattice(remaining - support, k, step, prefix, configs);
+        prefix.pop();
+        support += step;
+    }
+}
+
+/// Splits `n` agents proportionally to the weights. Agents left over by
+/// rounding down go to the largest remainders, ties to smaller opinions.
+fn apportion(n: u64, weights: &[f64]) -> Vec<u64> {
+    let total = weights.iter().sum::<f64>();
+    let quotas = weights
+        .iter()
+        .map(|weight| n as f64 * weight / total)
+        .collect::<Vec<_>>();
+    let mut config = quotas
+        .iter()
+        .map(|quota| quota.floor() as u64)
+        .collect::<Vec<_>>();
+    let mut order = (0..weights.len()).collect::<Vec<_>>();
+    order.sort_by(|a, b| (quotas[*b].fract()).total_cmp(&quotas[*a].fract()));
+    let assigned = config.iter().sum::<u64>();
+    for opinion in order
+        .into_iter()
+        .cycle()
+        .take(n.saturating_sub(assigned) as usize)
+    {
+        config[opinion] += 1;
+    }
+    config
+}
+
+/// Draws from a gamma distribution with unit scale after Marsaglia and Tsang.
+fn gamma<R: Rng>(shape: f64, rng: &mut R) -> f64 {
+    if shape < 1.0 {
+        let uniform: f64 = rng.gen();
+        return gamma(shape + 1.0, rng) * uniform.powf(1.0 / shape);
+    }
+    let d = shape - 1.0 / 3.0;
+    let c = 1.0 / (9.0 * d).sqrt();
+    loop {
+        let (x, v) = loop {
+            let x = normal(rng);
+            let v = 1.0 + c * x;
+            if v > 0.0 {
+                break (x, v * v * v);
+            }
+        };
+        let uniform: f64 = rng.gen();
+        if uniform.ln() < 0.5 * x * x + d - d * v + d * v.ln() {
+            return d * v;
+        }
+    }
+}
+
+/// Draws from the standard normal distribution via the Box-Muller transform.
+fn normal<R: Rng>(rng: &mut R) -> f64 {
+    let u: f64 = 1.0 - rng.gen::<f64>();
+    let v: f64 = rng.gen();
+    (-2.0 * u.ln()).sqrt() * (2.0 * std::f64::consts::PI * v).cos()
+}
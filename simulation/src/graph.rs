@@ -0,0 +1,271 @@
+use std::{collections::HashSet, fs::read_to_string};
+
+use rand::Rng;
+
+use crate::{error::SimulationError, topology::Topology};
+
+/// Undirected graph without self-loops and multi-edges, stored as adjacency
+/// arrays.
+#[derive(Debug)]
+pub struct Graph {
+    /// Neighbors of agent `i` are stored at `neighbors[offsets[i]..offsets[i + 1]]`
+    offsets: Vec<usize>,
+    neighbors: Vec<u32>,
+}
+
+impl Graph {
+    /// Builds a graph on `n` agents from a list of edges. Self-loops and
+    /// multi-edges are dropped.
+    pub fn from_edges(n: u64, edges: Vec<(u32, u32)>) -> Self {
+        let mut adjacency = vec![vec![]; n as usize];
+        for (first, second) in edges {
+            if first != second {
+                adjacency[first as usize].push(second);
+                adjacency[second as usize].push(first);
+            }
+        }
+
+        let mut offsets = vec![0];
+        let mut neighbors = vec![];
+        for mut agent_neighbors in adjacency {
+            agent_neighbors.sort_unstable();
+            agent_neighbors.dedup();
+            neighbors.append(&mut agent_neighbors);
+            offsets.push(neighbors.len());
+        }
+        Graph { offsets, neighbors }
+    }
+
+    /// Returns the neighbors of an agent.
+    pub fn neighbors(&self, agent: usize) -> &[u32] {
+        &self.neighbors[self.offsets[agent]..self.offsets[agent + 1]]
+    }
+
+    /// Loads a graph from a file containing one edge per line, given as two
+    /// agent indices separated by whitespace or a comma. Empty lines and
+    /// lines starting with `#` or `%` are skipped.
+    pub fn load(path: &str, n: u64) -> Result<Self, SimulationError> {
+        let mut edges = vec![];
+        for (line_number, line) in read_to_string(path)?.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with('%') {
+                continue;
+            }
+            let invalid_line = || {
+                SimulationError::InvalidTopology(format!(
+                    "line {} of {path} is not an edge between agents 0 to {}",
+                    line_number + 1,
+                    n - 1
+                ))
+            };
+            let agents = line
+                .split(|c: char| c.is_whitespace() || c == ',')
+                .filter(|agent| !agent.is_empty())
+                .map(|agent| agent.parse::<u64>().map_err(|_| invalid_line()))
+                .collect::<Result<Vec<_>, _>>()?;
+            match agents.as_slice() {
+                [first, second] if *first < n && *second < n => {
+                    edges.push((*first as u32, *second as u32))
+                }
+                _ => return Err(invalid_line()),
+            }
+        }
+        Ok(Graph::from_edges(n, edges))
+    }
+
+    /// Generates a graph of a random or regular topology. Returns `None` for
+    /// the complete topology and for graphs loaded from a file.
+    pub fn generate<R: Rng>(topology: &Topology, n: u64, rng: &mut R) -> Option<Self> {
+        let edges = match topology {
+            Topology::Complete | Topology::EdgeList { .. } => return None,
+            Topology::Ring { degree } => ring(n, *degree),
+            Topology::Torus { width } => torus(n, *width),
+            Topology::ErdosRenyi { probability } => {
+                let mut edges = vec![];
+                random_pairs(n * (n - 1) / 2, *probability, rng, |index| {
+                    let (first, second) = triangle_pair(index);
+                    edges.push((first as u32, second as u32));
+                });
+                edges
+            }
+            Topology::RandomRegular { degree } => random_regular(n, *degree, rng),
+            Topology::BarabasiAlbert { attachments } => barabasi_albert(n, *attachments, rng),
+            Topology::StochasticBlockModel {
+                blocks,
+                p_in,
+                p_out,
+            } => stochastic_block_model(n, *blocks as u64, *p_in, *p_out, rng),
+        };
+        Some(Graph::from_edges(n, edges))
+    }
+}
+
+fn ring(n: u64, degree: u32) -> Vec<(u32, u32)> {
+    let mut edges = vec![];
+    for agent in 0..n {
+        for distance in 1..=(degree / 2) as u64 {
+            edges.push((agent as u32, ((agent + distance) % n) as u32));
+        }
+    }
+    edges
+}
+
+fn torus(n: u64, width: u64) -> Vec<(u32, u32)> {
+    let height = n / width;
+    let mut edges = vec![];
+    for row in 0..height {
+        for column in 0..width {
+            let agent = (row * width + column) as u32;
+            let right = (row * width + (column + 1) % width) as u32;
+            let below = (((row + 1) % height) * width + column) as u32;
+            edges.push((agent, right));
+            edges.push((agent, below));
+        }
+    }
+    edges
+}
+
+/// Calls `select` for every index in `0..count` with the given probability.
+/// Skips over unselected indices geometrically, so the runtime is linear in
+/// the number of selected indices (Batagelj and Brandes, 2005).
+fn random_pairs<R: Rng, F: FnMut(u64)>(count: u64, probability: f64, rng: &mut R, mut select: F) {
+    if probability <= 0.0 {
+        return;
+    }
+    if probability >= 1.0 {
+        (0..count).for_each(select);
+        return;
+    }
+    let log_q = (1.0 - probability).ln();
+    let mut index: u64 = 0;
+    loop {
+        let skip = ((1.0 - rng.gen::<f64>()).ln() / log_q).floor() as u64;
+        index = match index.checked_add(skip) {
+            Some(index) if index < count => index,
+            _ => return,
+        };
+        select(index);
+        index += 1;
+    }
+}
+
+/// Maps an index to the pair `(v, w)` with `w < v` at this position in the
+/// enumeration (1, 0), (2, 0), (2, 1), (3, 0), ...
+fn triangle_pair(index: u64) -> (u64, u64) {
+    let mut v = ((1.0 + (1.0 + 8.0 * index as f64).sqrt()) / 2.0) as u64;
+    // Correct rounding errors of the floating point estimate
+    while v * (v - 1) / 2 > index {
+        v -= 1;
+    }
+    while (v + 1) * v / 2 <= index {
+        v += 1;
+    }
+    (v, index - v * (v - 1) / 2)
+}
+
+/// Pairs the `degree` stubs of every agent uniformly at random, rejecting
+/// pairs which would form self-loops or multi-edges. Restarts if no valid
+/// pair is found (Steger and Wormald, 1999).
+fn random_regular<R: Rng>(n: u64, degree: u32, rng: &mut R) -> Vec<(u32, u32)> {
+    const TRIES: usize = 100;
+    'restart: loop {
+        let mut stubs = (0..n as u32)
+            .flat_map(|agent| std::iter::repeat_n(agent, degree as usize))
+            .collect::<Vec<_>>();
+        let mut edges = HashSet::new();
+        while !stubs.is_empty() {
+            let mut paired = false;
+            for _ in 0..TRIES {
+                let first = rng.gen_range(0..stubs.len());
+                let second = rng.gen_range(0..stubs.len());
+                let edge = (
+                    stubs[first].min(stubs[second]),
+                    stubs[first].max(stubs[second]),
+                );
+                if first == second || edge.0 == edge.1 || edges.contains(&edge) {
+                    continue;
+                }
+                edges.insert(edge);
+                stubs.swap_remove(first.max(second));
+                stubs.swap_remove(first.min(second));
+                paired = true;
+                break;
+            }
+            if !paired {
+                continue 'restart;
+            }
+        }
+        let mut edges = edges.into_iter().collect::<Vec<_>>();
+        // Keep the graph independent of the iteration order of the set
+        edges.sort_unstable();
+        return edges;
+    }
+}
+
+/// Starts with a complete graph on `attachments + 1` agents. Every further
+/// agent connects to `attachments` distinct agents chosen proportionally to
+/// their degree.
+fn barabasi_albert<R: Rng>(n: u64, attachments: u32, rng: &mut R) -> Vec<(u32, u32)> {
+    let initial = attachments + 1;
+    let mut edges = vec![];
+    // Every agent occurs once per incident edge
+    let mut endpoints = vec![];
+    for first in 0..initial {
+        for second in 0..first {
+            edges.push((first, second));
+            endpoints.extend([first, second]);
+        }
+    }
+    for agent in initial..n as u32 {
+        let mut targets = HashSet::new();
+        let mut ordered_targets = vec![];
+        while targets.len() < attachments as usize {
+            let target = endpoints[rng.gen_range(0..endpoints.len())];
+            if targets.insert(target) {
+                ordered_targets.push(target);
+            }
+        }
+        for target in ordered_targets {
+            edges.push((agent, target));
+            endpoints.extend([agent, target]);
+        }
+    }
+    edges
+}
+
+/// Splits the agents into contiguous, equally sized blocks and connects pairs
+/// inside a block with probability `p_in` and other pairs with `p_out`.
+fn stochastic_block_model<R: Rng>(
+    n: u64,
+    blocks: u64,
+    p_in: f64,
+    p_out: f64,
+    rng: &mut R,
+) -> Vec<(u32, u32)> {
+    let block_start = |block: u64| block * n / blocks;
+    let mut edges = vec![];
+    for first_block in 0..blocks {
+        let first_start = block_start(first_block);
+        let first_size = block_start(first_block + 1) - first_start;
+        random_pairs(
+            first_size * first_size.saturating_sub(1) / 2,
+            p_in,
+            rng,
+            |index| {
+                let (first, second) = triangle_pair(index);
+                edges.push(((first_start + first) as u32, (first_start + second) as u32));
+            },
+        );
+        for second_block in (first_block + 1)..blocks {
+            let second_start = block_start(second_block);
+            let second_size = block_start(second_block + 1) - second_start;
+            random_pairs(first_size * second_size, p_out, rng, |index| {
+                edges.push((
+                    (first_start + index / second_size) as u32,
+                    (second_start + index % second_size) as u32,
+                ));
+            });
+        }
+    }
+    edges
+}
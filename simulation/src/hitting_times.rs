@@ -0,0 +1,73 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::observer::Observation;
+
+/// Interaction counts at which a simulation first reached given states.
+/// Separates the elimination of opinions from the endgame of the process.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct HittingTimes {
+    /// First interaction count at which some opinion was held by the given
+    /// share of agents, by share
+    pub shares: BTreeMap<String, u64>,
+    /// First interaction count at which at most the given number of opinions
+    /// survived, from k - 1 down to 1
+    pub surviving_opinions: BTreeMap<u16, u64>,
+    /// Shares not reached yet, in ascending order
+    #[serde(skip_deserializing, skip_serializing)]
+    pending: Vec<f64>,
+    /// Lowest number of surviving opinions recorded so far
+    #[serde(skip_deserializing, skip_serializing)]
+    lowest_level: u16,
+}
+
+impl HittingTimes {
+    pub fn new(thresholds: &[f64], k: u16) -> Self {
+        let mut pending = thresholds.to_vec();
+        pending.sort_by(f64::total_cmp);
+        pending.dedup();
+        HittingTimes {
+            pending,
+            lowest_level: k,
+            ..Default::default()
+        }
+    }
+
+    /// Records the hitting times of all states reached by the observed
+    /// distribution for the first time. Called after every interaction, so
+    /// it only reads the counts and touches the levels reached newly.
+    pub fn observe(&mut self, observation: &Observation) {
+        if self.pending.is_empty() && self.lowest_level <= 1 {
+            return;
+        }
+        // Further states of the protocol, e.g. undecided, are no opinions
+        let (surviving, max_count) = (0..observation.k)
+            .map(|opinion| observation.opinion_distribution.get(opinion))
+            .fold((0, 0), |(surviving, max_count), count| {
+                (surviving + (count > 0) as u16, max_count.max(count))
+            });
+        // Several opinions may die out at once, all levels down to the
+        // current number of surviving opinions are reached then
+        let level = surviving.max(1);
+        if self.pending.is_empty() && level >= self.lowest_level {
+            return;
+        }
+        for level in level..self.lowest_level {
+            self.surviving_opinions
+                .insert(level, observation.interaction_count);
+        }
+        self.lowest_level = self.lowest_level.min(level);
+
+        let max_share = max_count as f64 / observation.n as f64;
+        let reached = self
+            .pending
+            .iter()
+            .take_while(|threshold| max_share >= **threshold)
+            .count();
+        for threshold in self.pending.drain(..reached) {
+            self.shares
+                .insert(threshold.to_string(), observation.interaction_count);
+        }
+    }
+}
@@ -1,21 +1,59 @@
+use std::{collections::BTreeMap, sync::Arc};
+
 use clap::ValueEnum;
-use error::SimulationError;
 use opinion_distribution::OpinionDistribution;
-use rand::{rngs::ThreadRng, seq::SliceRandom, Rng};
+use rand::{seq::SliceRandom, Rng};
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 
+use adversary::Adversary;
 use agent::Agent;
 use config::Config;
+use error::SimulationError;
+use generator::ConfigGenerator;
+use graph::Graph;
+use hitting_times::HittingTimes;
+use noise::Noise;
+use observer::{Metric, MetricObserver, Observation, Observer};
+use protocol::{Protocol, ProtocolKind};
+use recorder::{Cadence, Recorder, Snapshot};
+use rng::{derive_seed, RngAlgorithm, SimulationRng};
+use sampling::Sampling;
+use tally::Tally;
+use tie_break::TieBreak;
+use topology::Topology;
 
+pub mod adversary;
 mod agent;
 pub mod config;
-mod error;
-mod opinion_distribution;
+pub mod error;
+pub mod generator;
+pub mod graph;
+pub mod hitting_times;
+pub mod noise;
+pub mod observer;
+pub mod opinion_distribution;
+pub mod protocol;
+pub mod recorder;
+pub mod rng;
+pub mod sampling;
+pub mod tally;
+pub mod tie_break;
+pub mod topology;
+
+/// Number of agents updated with the same random number generator in a
+/// parallel round. Fixed, so that results do not depend on the number of
+/// threads.
+const CHUNK_SIZE: usize = 4096;
 
 #[derive(Clone, Debug, Deserialize, Serialize, ValueEnum, PartialEq)]
 pub enum Model {
+    /// All agents update simultaneously in synchronous rounds
     Gossip,
+    /// A single uniformly chosen agent updates per interaction
     Population,
+    /// Every agent updates at the ticks of its own rate-1 Poisson clock
+    Poisson,
 }
 
 impl std::fmt::Display for Model {
@@ -23,10 +61,40 @@ impl std::fmt::Display for Model {
         match self {
             Model::Gossip => write!(f, "gossip"),
             Model::Population => write!(f, "population"),
+            Model::Poisson => write!(f, "poisson"),
+        }
+    }
+}
+
+/// Defines how the state of the agents is stored during a simulation.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ValueEnum, PartialEq)]
+pub enum Engine {
+    /// Stores one agent per agent
+    #[default]
+    Agent,
+    /// Stores the opinion counts only, memory is independent of n
+    Count,
+}
+
+impl std::fmt::Display for Engine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Engine::Agent => write!(f, "agent"),
+            Engine::Count => write!(f, "count"),
         }
     }
 }
 
+/// Buffers reused by all interactions of a simulation, so that interactions
+/// allocate nothing.
+#[derive(Clone, Debug, Default)]
+struct Scratch {
+    /// Indices of the sampled agents
+    indices: Vec<usize>,
+    /// Number of sampled agents per state
+    sample: Tally,
+}
+
 impl PartialEq for Simulation {
     fn eq(&self, other: &Self) -> bool {
         self.n == other.n
@@ -34,6 +102,14 @@ impl PartialEq for Simulation {
             && self.k == other.k
             && self.config == other.config
             && self.model == other.model
+            && self.protocol == other.protocol
+            && self.noise == other.noise
+            && self.adversary == other.adversary
+            && self.tie_break == other.tie_break
+            && self.sampling() == other.sampling()
+            && self.topology == other.topology
+            && self.stubborn == other.stubborn
+            && self.consensus_fraction == other.consensus_fraction
     }
 }
 
@@ -42,6 +118,9 @@ pub struct Simulation {
     /// Collection of agents
     #[serde(skip_deserializing, skip_serializing)]
     pub agents: Vec<Agent>,
+    /// Agents of the previous round in the gossip model
+    #[serde(skip_deserializing, skip_serializing)]
+    previous_agents: Vec<Agent>,
     /// Number of agents
     pub n: u64,
     /// Sample size
@@ -50,17 +129,135 @@ pub struct Simulation {
     pub k: u16,
     /// Initial configuration
     pub config: Vec<u64>,
+    /// Generator which produced the initial configuration, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config_generator: Option<ConfigGenerator>,
     /// Stores number of occurences for each opinion
     #[serde(skip_deserializing, skip_serializing)]
     pub opinion_distribution: OpinionDistribution,
     /// Number of interactions
     pub interaction_count: u64,
-    // pub entropy: Vec<(u64, f64)>,
     pub model: Model,
+    /// Total number of agent updates, counting n per round in the gossip
+    /// model
+    #[serde(default)]
+    pub interactions: u64,
+    /// Agent updates divided by n
+    #[serde(default)]
+    pub parallel_time: f64,
+    /// Elapsed continuous time, only tracked in the Poisson model
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time: Option<f64>,
+    /// Engine storing the state of the agents
+    #[serde(default)]
+    pub engine: Engine,
+    /// Whether the rounds of the gossip model are computed in parallel
+    #[serde(default)]
+    pub parallel_rounds: bool,
+    /// Seed of the random number generator
+    #[serde(default)]
+    pub seed: u64,
+    /// Algorithm of the random number generator
+    #[serde(default)]
+    pub rng: RngAlgorithm,
+    /// Cadence of opinion distribution snapshots
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recording: Option<Cadence>,
+    /// Snapshots of the opinion distribution taken during the run
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub trajectory: Vec<Snapshot>,
+    #[serde(skip_deserializing, skip_serializing)]
+    recorder: Option<Recorder>,
+    /// Values collected by the observers, stored by observer name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metrics: BTreeMap<String, Vec<(u64, f64)>>,
+    #[serde(skip_deserializing, skip_serializing)]
+    observers: Vec<Box<dyn Observer>>,
+    /// Maximum number of interactions before the simulation is stopped
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_interactions: Option<u64>,
+    /// Whether the simulation stopped before reaching consensus
+    #[serde(default)]
+    pub censored: bool,
+    /// First interaction counts at which opinion shares and numbers of
+    /// surviving opinions were reached
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hitting_times: Option<HittingTimes>,
+    /// Opinions in the order in which their support first dropped to zero,
+    /// together with the interaction count at which this happened
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extinctions: Vec<(u16, u64)>,
+    /// Whether the simulation stopped because all agents were undecided
+    #[serde(default)]
+    pub undecided: bool,
+    /// Opinion the agents agreed on, not set without consensus
+    #[serde(default)]
+    pub consensus_opinion: Option<u16>,
+    /// Most frequent opinion of the initial configuration, not set if
+    /// several opinions are most frequent
+    #[serde(default)]
+    pub initial_plurality: Option<u16>,
+    /// Whether the agents agreed on the initial plurality opinion
+    #[serde(default)]
+    pub plurality_won: bool,
+    #[serde(skip_deserializing, skip_serializing)]
+    started: bool,
+    #[serde(skip_deserializing, skip_serializing)]
+    generator: Option<SimulationRng>,
+    #[serde(skip_deserializing, skip_serializing)]
+    scratch: Scratch,
+    /// Update rule of the agents
+    #[serde(default)]
+    pub protocol: ProtocolKind,
+    /// Noise perturbing the updates of the agents
+    #[serde(default)]
+    pub noise: Noise,
+    /// Mean share of the most frequent opinion per round over the second
+    /// half of the run, only tracked with noise
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub majority_share: Option<f64>,
+    #[serde(skip_deserializing, skip_serializing)]
+    majority_observer: Option<MetricObserver>,
+    /// Byzantine agents corrupting the samples of honest agents
+    #[serde(default)]
+    pub adversary: Adversary,
+    /// Rule deciding the new opinion on a tie
+    #[serde(default)]
+    pub tie_break: TieBreak,
+    /// Defines how agents draw their sample, not set in records of versions
+    /// without explicit sampling modes
+    #[serde(default)]
+    pub sampling: Option<Sampling>,
+    /// Graph on which the agents interact
+    #[serde(default)]
+    pub topology: Topology,
+    #[serde(skip_deserializing, skip_serializing)]
+    graph: Option<Arc<Graph>>,
+    /// Node of the graph occupied by each agent, Byzantine agents last
+    #[serde(skip_deserializing, skip_serializing)]
+    nodes: Vec<u32>,
+    /// Agent occupying each node of the graph
+    #[serde(skip_deserializing, skip_serializing)]
+    occupants: Vec<u32>,
+    /// Number of stubborn agents per opinion
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stubborn: Vec<u64>,
+    /// Share of agents which have to agree on an opinion to reach consensus
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub consensus_fraction: Option<f64>,
 }
 
 impl Simulation {
     pub fn new(config: Config) -> Result<Self, SimulationError> {
+        config.validate()?;
+        let sampling = config.sampling();
+        // Graphs loaded from a file are shared by all clones of the
+        // simulation, random graphs are generated per run on the first step
+        let graph = match &config.topology {
+            Topology::EdgeList { path } => Some(Arc::new(Graph::load(path, config.n)?)),
+            _ => None,
+        };
+
         let mut agents = vec![];
         let mut opinion_distribution = OpinionDistribution::default();
         let choices = (0..config.k).collect::<Vec<u16>>();
@@ -70,91 +267,620 @@ impl Simulation {
             .collect::<Vec<_>>();
         for (opinion, weight) in weighted_choices {
             opinion_distribution.batch(opinion, weight);
-            for _ in 0..weight {
-                agents.push(Agent::new(opinion));
+            if config.engine == Engine::Count {
+                continue;
+            }
+            let stubborn = config.stubborn.get(opinion as usize).copied().unwrap_or(0);
+            for index in 0..weight {
+                if index < stubborn {
+                    agents.push(Agent::stubborn(opinion));
+                } else {
+                    agents.push(Agent::new(opinion));
+                }
             }
         }
 
+        let recorder = config
+            .recording
+            .clone()
+            .map(|cadence| Recorder::new(cadence, &config.model, config.n));
+        let observers = config
+            .metrics
+            .into_iter()
+            .map(|metric| {
+                Box::new(MetricObserver::new(metric, &config.model, config.n)) as Box<dyn Observer>
+            })
+            .collect();
+        // Under noise consensus may never be reached, so track the share of
+        // the majority instead
+        let majority_observer = config
+            .noise
+            .is_enabled()
+            .then(|| MetricObserver::new(Metric::MaxShare, &config.model, config.n));
+
+        let initial_plurality = plurality(&config.config);
+        let extinctions = (0..config.k)
+            .filter(|opinion| opinion_distribution.get(*opinion) == 0)
+            .map(|opinion| (opinion, 0))
+            .collect();
+
         Ok(Simulation {
             agents,
+            previous_agents: vec![],
             n: config.n,
             j: config.j,
             k: config.k,
             config: config.config,
+            config_generator: config.config_generator,
             opinion_distribution,
             interaction_count: 0,
-            // entropy: vec![],
+            interactions: 0,
+            parallel_time: 0.0,
+            time: (config.model == Model::Poisson).then_some(0.0),
             model: config.model,
+            engine: config.engine,
+            parallel_rounds: config.parallel_rounds,
+            seed: config.seed,
+            rng: config.rng,
+            recording: config.recording,
+            trajectory: vec![],
+            recorder,
+            metrics: BTreeMap::new(),
+            observers,
+            max_interactions: config.max_interactions,
+            censored: false,
+            hitting_times: (!config.thresholds.is_empty())
+                .then(|| HittingTimes::new(&config.thresholds, config.k)),
+            extinctions,
+            undecided: false,
+            consensus_opinion: None,
+            initial_plurality,
+            plurality_won: false,
+            started: false,
+            generator: None,
+            scratch: Scratch::default(),
+            protocol: config.protocol,
+            noise: config.noise,
+            majority_share: None,
+            majority_observer,
+            adversary: config.adversary,
+            tie_break: config.tie_break,
+            sampling: Some(sampling),
+            topology: config.topology,
+            graph,
+            nodes: vec![],
+            occupants: vec![],
+            stubborn: config.stubborn,
+            consensus_fraction: config.consensus_fraction,
         })
     }
 
+    /// Attaches an observer which is called during the simulation. Its
+    /// values are stored under its name once the simulation finished.
+    pub fn attach_observer(&mut self, observer: Box<dyn Observer>) {
+        self.observers.push(observer);
+    }
+
+    /// Sets the seed of the random number generator. Has to be called
+    /// before the first step to take effect for the whole run.
+    pub fn reseed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.generator = None;
+    }
+
     /// Starts the simulation loop and exits if all agents agree on the
-    /// same opinion.
+    /// same opinion or the interaction budget is exhausted. Runs with the
+    /// same seed are identical.
     pub fn execute(&mut self) {
-        let mut rng = rand::thread_rng();
-        match self.model {
-            Model::Gossip => {
-                while !self.reached_consensus() {
-                    // self.calculate_entropy();
-                    self.interact_gossip_model(&mut rng);
+        self.run_until(|_| false);
+        self.finish();
+    }
+
+    /// Executes a single interaction in the population model, a single
+    /// clock tick in the Poisson model or a single round in the gossip model.
+    pub fn step(&mut self) {
+        let mut rng = self
+            .generator
+            .take()
+            .unwrap_or_else(|| SimulationRng::new(&self.rng, self.seed));
+        if !self.started {
+            self.started = true;
+            self.place_agents(&mut rng);
+            self.record();
+            self.observe();
+        }
+        if let Some(time) = &mut self.time {
+            // The n clocks together tick at rate n, and each tick belongs to
+            // a uniformly chosen agent
+            *time += -(1.0 - rng.gen::<f64>()).ln() / self.n as f64;
+        }
+        let mut scratch = std::mem::take(&mut self.scratch);
+        match (&self.model, &self.engine) {
+            (Model::Gossip, Engine::Agent) if self.parallel_rounds => {
+                self.interact_gossip_model_parallel(&mut rng)
+            }
+            (Model::Gossip, Engine::Agent) => self.interact_gossip_model(&mut scratch, &mut rng),
+            (Model::Gossip, Engine::Count) => {
+                self.interact_gossip_model_counts(&mut scratch, &mut rng)
+            }
+            (Model::Population | Model::Poisson, Engine::Agent) => {
+                self.interact_population_model(&mut scratch, &mut rng)
+            }
+            (Model::Population | Model::Poisson, Engine::Count) => {
+                self.interact_population_model_counts(&mut scratch, &mut rng)
+            }
+        }
+        self.scratch = scratch;
+        self.generator = Some(rng);
+        self.interactions = self.agent_updates();
+        self.parallel_time = self.interactions as f64 / self.n as f64;
+        self.record_extinctions();
+        self.record();
+        self.observe();
+    }
+
+    /// Generates the graph of random topologies and places all agents,
+    /// Byzantine ones included, on it in random order. Agents are created
+    /// grouped by opinion, which would otherwise cluster opinions on the
+    /// graph, and Byzantine agents would occupy the last nodes, e.g. a whole
+    /// block.
+    fn place_agents<R: Rng>(&mut self, rng: &mut R) {
+        if self.topology == Topology::Complete {
+            return;
+        }
+        if self.graph.is_none() {
+            self.graph = Graph::generate(&self.topology, self.n, rng).map(Arc::new);
+        }
+        self.occupants = (0..self.n as u32).collect();
+        self.occupants.shuffle(rng);
+        self.nodes = vec![0; self.n as usize];
+        for (node, agent) in self.occupants.iter().enumerate() {
+            self.nodes[*agent as usize] = node as u32;
+        }
+    }
+
+    /// Returns how agents draw their sample. Records without sampling mode
+    /// used the default of their model.
+    pub fn sampling(&self) -> Sampling {
+        self.sampling
+            .unwrap_or_else(|| Sampling::default_for(&self.model))
+    }
+
+    /// Draws the sample of the agent at index `chosen` out of `agents` and
+    /// counts its opinions into the scratch sample.
+    fn draw_sample<R: Rng>(
+        &self,
+        chosen: usize,
+        agents: &[Agent],
+        opinion_distribution: &OpinionDistribution,
+        scratch: &mut Scratch,
+        rng: &mut R,
+    ) {
+        let j = self.j as usize;
+        match &self.graph {
+            Some(graph) => {
+                let node = self.nodes[chosen] as usize;
+                self.sampling().draw_neighbors(
+                    graph.neighbors(node),
+                    j,
+                    node,
+                    &mut scratch.indices,
+                    rng,
+                );
+                for index in scratch.indices.iter_mut() {
+                    *index = self.occupants[*index] as usize;
                 }
             }
-            Model::Population => {
-                while !self.reached_consensus() {
-                    // if self.interaction_count % self.n == 0 {
-                    //     // self.calculate_entropy();
-                    // }
-                    self.interact_population_model(&mut rng);
+            None => {
+                self.sampling()
+                    .draw_indices(self.n as usize, j, chosen, &mut scratch.indices, rng)
+            }
+        }
+        self.count_sample(agents, opinion_distribution, scratch, rng);
+    }
+
+    /// Executes the given number of interactions (rounds in the gossip
+    /// model). Stops early on consensus or if the budget is exhausted.
+    pub fn run_for(&mut self, interactions: u64) {
+        let target = self.interaction_count.saturating_add(interactions);
+        self.run_until(|simulation| simulation.interaction_count >= target);
+    }
+
+    /// Executes steps until the predicate holds. Stops early on consensus or
+    /// if the budget is exhausted.
+    pub fn run_until<P: FnMut(&Simulation) -> bool>(&mut self, mut predicate: P) {
+        while !self.reached_consensus() && !self.exhausted_budget() && !predicate(self) {
+            self.step();
+        }
+    }
+
+    /// Completes the trajectory and the values of all observers and marks
+    /// the simulation as censored if it did not reach consensus.
+    pub fn finish(&mut self) {
+        self.record_final();
+        self.finish_observers();
+        self.censored = !self.reached_consensus();
+        self.undecided = self.all_undecided();
+        if !self.censored && !self.undecided {
+            // Without consensus fraction, stubborn agents may hold other
+            // opinions than the consensus
+            let mut counts = self.opinion_distribution.to_vec(self.k);
+            if self.consensus_fraction.is_none() {
+                for (opinion, count) in counts.iter_mut().enumerate() {
+                    *count -= self.stubborn_count(opinion as u16);
                 }
             }
+            self.consensus_opinion = plurality(&counts);
+        }
+        self.plurality_won =
+            self.consensus_opinion.is_some() && self.consensus_opinion == self.initial_plurality;
+    }
+
+    /// Returns the total number of agent updates. A round of the gossip
+    /// model updates all n agents.
+    pub fn agent_updates(&self) -> u64 {
+        match self.model {
+            Model::Gossip => self.interaction_count * self.n,
+            Model::Population | Model::Poisson => self.interaction_count,
+        }
+    }
+
+    /// Returns whether the maximum number of interactions is reached.
+    pub fn exhausted_budget(&self) -> bool {
+        self.max_interactions
+            .is_some_and(|max_interactions| self.interaction_count >= max_interactions)
+    }
+
+    /// Calls all attached observers with the current state.
+    fn observe(&mut self) {
+        let observation = Observation {
+            n: self.honest_agents(),
+            k: self.k,
+            interaction_count: self.interaction_count,
+            opinion_distribution: &self.opinion_distribution,
+        };
+        for observer in self.observers.iter_mut() {
+            observer.observe(&observation);
+        }
+        if let Some(observer) = &mut self.majority_observer {
+            observer.observe(&observation);
+        }
+        if let Some(hitting_times) = &mut self.hitting_times {
+            hitting_times.observe(&observation);
+        }
+    }
+
+    /// Finishes all attached observers and stores their values.
+    fn finish_observers(&mut self) {
+        let observation = Observation {
+            n: self.honest_agents(),
+            k: self.k,
+            interaction_count: self.interaction_count,
+            opinion_distribution: &self.opinion_distribution,
+        };
+        for observer in self.observers.iter_mut() {
+            observer.finish(&observation);
+            self.metrics.insert(observer.name(), observer.values());
+        }
+        if let Some(observer) = &mut self.majority_observer {
+            observer.finish(&observation);
+            // Leave out the first half of the run, in which the process
+            // still moves away from the initial configuration
+            let values = observer.values();
+            let long_run = &values[values.len() / 2..];
+            self.majority_share =
+                Some(long_run.iter().map(|(_, share)| share).sum::<f64>() / long_run.len() as f64);
+        }
+        if let Some(hitting_times) = &mut self.hitting_times {
+            hitting_times.observe(&observation);
+        }
+    }
+
+    /// Stores the opinions which died out during the last step. Opinions
+    /// reviving later, e.g. through noise, keep their first extinction.
+    fn record_extinctions(&mut self) {
+        for opinion in self.opinion_distribution.drain_extinct() {
+            if opinion < self.k
+                && self
+                    .extinctions
+                    .iter()
+                    .all(|(extinct, _)| *extinct != opinion)
+            {
+                self.extinctions.push((opinion, self.interaction_count));
+            }
+        }
+    }
+
+    /// Stores a snapshot of the opinion distribution if one is due.
+    fn record(&mut self) {
+        if let Some(recorder) = &mut self.recorder {
+            if let Some(snapshot) =
+                recorder.record(self.interaction_count, &self.opinion_distribution, self.k)
+            {
+                self.trajectory.push(snapshot);
+            }
+        }
+    }
+
+    /// Ends the trajectory with the final opinion distribution.
+    fn record_final(&mut self) {
+        let last_recorded = self
+            .trajectory
+            .last()
+            .map(|snapshot| snapshot.interaction_count);
+        if self.recorder.is_some() && last_recorded != Some(self.interaction_count) {
+            self.trajectory.push(Snapshot {
+                interaction_count: self.interaction_count,
+                opinion_distribution: self.opinion_distribution.to_vec(self.k),
+            });
+        }
+    }
+
+    /// Counts the opinions of the sampled agents. Indices beyond the honest
+    /// agents belong to Byzantine agents, whose reports are chosen by the
+    /// adversary.
+    fn count_sample<R: Rng>(
+        &self,
+        agents: &[Agent],
+        opinion_distribution: &OpinionDistribution,
+        scratch: &mut Scratch,
+        rng: &mut R,
+    ) {
+        scratch.sample.clear();
+        let mut byzantine = 0;
+        for index in &scratch.indices {
+            match agents.get(*index) {
+                Some(agent) => scratch.sample.add(agent.opinion),
+                None => byzantine += 1,
+            }
+        }
+        self.adversary.corrupt(
+            &mut scratch.sample,
+            byzantine,
+            opinion_distribution,
+            self.k,
+            rng,
+        );
+    }
+
+    fn interact_population_model<R: Rng>(&mut self, scratch: &mut Scratch, rng: &mut R) {
+        let chosen = rng.gen_range(0..self.n as usize);
+        // Byzantine agents never update
+        if chosen < self.agents.len() {
+            self.draw_sample(
+                chosen,
+                &self.agents,
+                &self.opinion_distribution,
+                scratch,
+                rng,
+            );
+            self.agents[chosen].update(
+                &scratch.sample,
+                &mut self.opinion_distribution,
+                &self.protocol,
+                &self.noise,
+                self.k,
+                &self.tie_break,
+                rng,
+            );
+        }
+        self.interaction_count += 1;
+    }
+
+    /// Moves the current agents into the buffer of the previous round and
+    /// copies them back, reusing the allocations of both vectors.
+    fn swap_agents(&mut self) {
+        std::mem::swap(&mut self.agents, &mut self.previous_agents);
+        self.agents.clone_from(&self.previous_agents);
+    }
+
+    fn interact_gossip_model<R: Rng>(&mut self, scratch: &mut Scratch, rng: &mut R) {
+        self.swap_agents();
+        let old_distribution = self.opinion_distribution.clone();
+        for index in 0..self.agents.len() {
+            self.draw_sample(
+                index,
+                &self.previous_agents,
+                &old_distribution,
+                scratch,
+                rng,
+            );
+            self.agents[index].update(
+                &scratch.sample,
+                &mut self.opinion_distribution,
+                &self.protocol,
+                &self.noise,
+                self.k,
+                &self.tie_break,
+                rng,
+            );
         }
+        self.interaction_count += 1;
     }
 
-    fn interact_population_model(&mut self, rng: &mut ThreadRng) {
-        // Swap a random agent to the first position. This way we can always
-        // split the vector via `.split_first_mut()` to work via references.
-        self.agents.swap(0, rng.gen_range(0..self.n as usize));
-        if let Some((chosen_agent, remaining)) = self.agents.split_first_mut() {
-            let sample = remaining
-                .choose_multiple(rng, self.j as usize)
-                .collect::<Vec<_>>();
+    /// Computes a gossip round on all cores. Agents are split into chunks of
+    /// fixed size, each updated with its own random number generator seeded
+    /// from the generator of the simulation. Runs with the same seed are
+    /// identical for any number of threads.
+    fn interact_gossip_model_parallel<R: Rng>(&mut self, rng: &mut R) {
+        let round_seed = rng.gen::<u64>();
+        self.swap_agents();
+        let mut agents = std::mem::take(&mut self.agents);
+        let old_distribution = self.opinion_distribution.clone();
+        let simulation = &*self;
+        let distributions = agents
+            .par_chunks_mut(CHUNK_SIZE)
+            .enumerate()
+            .map(|(chunk, chunk_agents)| {
+                let mut rng =
+                    SimulationRng::new(&simulation.rng, derive_seed(round_seed, chunk as u64));
+                // Every chunk updates its own copy of the distribution, the
+                // changes are merged afterwards
+                let mut distribution = old_distribution.clone();
+                let mut scratch = Scratch::default();
+                for (offset, agent) in chunk_agents.iter_mut().enumerate() {
+                    simulation.draw_sample(
+                        chunk * CHUNK_SIZE + offset,
+                        &simulation.previous_agents,
+                        &old_distribution,
+                        &mut scratch,
+                        &mut rng,
+                    );
+                    agent.update(
+                        &scratch.sample,
+                        &mut distribution,
+                        &simulation.protocol,
+                        &simulation.noise,
+                        simulation.k,
+                        &simulation.tie_break,
+                        &mut rng,
+                    );
+                }
+                distribution
+            })
+            .collect::<Vec<_>>();
+        for distribution in &distributions {
+            self.opinion_distribution
+                .merge(&old_distribution, distribution);
+        }
+        self.agents = agents;
+        self.interaction_count += 1;
+    }
 
-            chosen_agent.update(sample, &mut self.opinion_distribution);
+    fn interact_population_model_counts<R: Rng>(&mut self, scratch: &mut Scratch, rng: &mut R) {
+        // Agents are exchangeable, so drawing the opinion of the chosen agent
+        // and the opinions of its sample from the counts is equivalent to
+        // drawing the agents themselves.
+        let states = self.protocol.states(self.k);
+        let honest_agents = self.honest_agents();
+        // Byzantine agents never update
+        if honest_agents < self.n && rng.gen_range(0..self.n) >= honest_agents {
+            self.interaction_count += 1;
+            return;
+        }
+        let opinion = self
+            .opinion_distribution
+            .choose_opinion(states, honest_agents, rng);
+        // The chosen agent is stubborn with the share of stubborn agents
+        // among the agents holding its opinion
+        let stubborn = self.stubborn_count(opinion);
+        if stubborn > 0 && rng.gen_range(0..self.opinion_distribution.get(opinion)) < stubborn {
             self.interaction_count += 1;
+            return;
+        }
+        let byzantine = self.opinion_distribution.sample(
+            states,
+            self.n,
+            self.j,
+            opinion,
+            &self.sampling(),
+            &mut scratch.sample,
+            rng,
+        );
+        self.adversary.corrupt(
+            &mut scratch.sample,
+            byzantine,
+            &self.opinion_distribution,
+            self.k,
+            rng,
+        );
+
+        let new_opinion = self.noise.apply(self.k, rng, |rng| {
+            self.protocol
+                .update(&scratch.sample, opinion, self.k, &self.tie_break, rng)
+        });
+        if let Some(new_opinion) = new_opinion {
+            self.opinion_distribution.update(Some(opinion), new_opinion);
         }
+        self.interaction_count += 1;
     }
 
-    fn interact_gossip_model(&mut self, rng: &mut ThreadRng) {
-        let old_agents = self.agents.clone();
-        for chosen_agent in self.agents.iter_mut() {
-            let sample = old_agents
-                .choose_multiple(rng, self.j as usize)
-                .collect::<Vec<_>>();
-            chosen_agent.update(sample, &mut self.opinion_distribution);
+    fn interact_gossip_model_counts<R: Rng>(&mut self, scratch: &mut Scratch, rng: &mut R) {
+        let old_distribution = self.opinion_distribution.clone();
+        let states = self.protocol.states(self.k);
+        for opinion in 0..states {
+            // Stubborn agents are sampled but never update
+            for _ in 0..old_distribution.get(opinion) - self.stubborn_count(opinion) {
+                let byzantine = old_distribution.sample(
+                    states,
+                    self.n,
+                    self.j,
+                    opinion,
+                    &self.sampling(),
+                    &mut scratch.sample,
+                    rng,
+                );
+                self.adversary.corrupt(
+                    &mut scratch.sample,
+                    byzantine,
+                    &old_distribution,
+                    self.k,
+                    rng,
+                );
+                let new_opinion = self.noise.apply(self.k, rng, |rng| {
+                    self.protocol
+                        .update(&scratch.sample, opinion, self.k, &self.tie_break, rng)
+                });
+                if let Some(new_opinion) = new_opinion {
+                    self.opinion_distribution.update(Some(opinion), new_opinion);
+                }
+            }
         }
         self.interaction_count += 1;
     }
 
-    // fn calculate_entropy(&mut self) {
-    //     let opinion_percentages = self
-    //         .opinion_distribution
-    //         .map
-    //         .values()
-    //         .map(|agents_with_opinion| *agents_with_opinion as f64 / self.n as f64)
-    //         .collect::<Vec<f64>>();
-    //     let mut entropy = 0.0;
-    //     for percentage in opinion_percentages.into_iter() {
-    //         if percentage != 0.0 {
-    //             entropy -= percentage * percentage.log(self.k as f64);
-    //         }
-    //     }
-    //     self.entropy.push((self.interaction_count, entropy));
-    // }
-
-    fn reached_consensus(&mut self) -> bool {
-        if self.opinion_distribution.check_occurence_with(self.n) {
+    /// Returns the number of stubborn agents holding the given opinion.
+    fn stubborn_count(&self, opinion: u16) -> u64 {
+        self.stubborn.get(opinion as usize).copied().unwrap_or(0)
+    }
+
+    /// Returns whether all agents are undecided. Without stubborn and
+    /// Byzantine agents, no agent ever leaves this state.
+    fn all_undecided(&self) -> bool {
+        matches!(self.protocol, ProtocolKind::UndecidedState)
+            && self.adversary.size == 0
+            && self.stubborn.iter().all(|count| *count == 0)
+            && self.opinion_distribution.get(self.k) == self.n
+    }
+
+    /// Returns the number of agents which are not Byzantine.
+    pub fn honest_agents(&self) -> u64 {
+        self.n - self.adversary.size
+    }
+
+    /// Returns whether all honest, non-stubborn agents agree on the same
+    /// opinion or, if a consensus fraction is set, whether enough honest
+    /// agents agree on one. A simulation in which all agents are undecided
+    /// stops as well.
+    pub fn reached_consensus(&self) -> bool {
+        if self.all_undecided() {
             return true;
         }
-        false
+        let honest_agents = self.honest_agents();
+        if let Some(fraction) = self.consensus_fraction {
+            let required = (fraction * honest_agents as f64).ceil() as u64;
+            return (0..self.k).any(|opinion| self.opinion_distribution.get(opinion) >= required);
+        }
+        if self.stubborn.is_empty() {
+            // Byzantine agents lead undecided agents back to an opinion
+            return self
+                .opinion_distribution
+                .check_occurence_with(honest_agents)
+                && self.opinion_distribution.get(self.k) != honest_agents;
+        }
+        let free_agents = honest_agents - self.stubborn.iter().sum::<u64>();
+        (0..self.k).any(|opinion| {
+            self.opinion_distribution.get(opinion) - self.stubborn_count(opinion) == free_agents
+        })
+    }
+}
+
+/// Returns the opinion with the most agents, if it is unique.
+fn plurality(counts: &[u64]) -> Option<u16> {
+    let max_count = counts.iter().max()?;
+    let mut opinions = (0..counts.len()).filter(|opinion| counts[*opinion] == *max_count);
+    match (opinions.next(), opinions.next()) {
+        (Some(opinion), None) => Some(opinion as u16),
+        _ => None,
     }
 }
@@ -0,0 +1,59 @@
+use clap::ValueEnum;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// Perturbs the updates of the agents. With the given probability an
+/// updating agent ignores its sample. Disabled by default.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+pub struct Noise {
+    /// Probability that an update is replaced by noise
+    pub probability: f64,
+    /// What a perturbed agent does instead of the update
+    pub mode: NoiseMode,
+}
+
+/// Behavior of an agent whose update is replaced by noise.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ValueEnum, PartialEq)]
+pub enum NoiseMode {
+    /// Adopt an opinion chosen uniformly at random out of all k opinions
+    #[default]
+    RandomOpinion,
+    /// Keep the own opinion
+    KeepOwn,
+}
+
+impl std::fmt::Display for NoiseMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NoiseMode::RandomOpinion => write!(f, "random-opinion"),
+            NoiseMode::KeepOwn => write!(f, "keep-own"),
+        }
+    }
+}
+
+impl Noise {
+    /// Returns whether updates are perturbed at all.
+    pub fn is_enabled(&self) -> bool {
+        self.probability > 0.0
+    }
+
+    /// Returns the new opinion of an updating agent. With the noise
+    /// probability the noise mode decides, otherwise the given update.
+    pub fn apply<R: Rng + ?Sized, F: FnOnce(&mut R) -> Option<u16>>(
+        &self,
+        k: u16,
+        rng: &mut R,
+        update: F,
+    ) -> Option<u16> {
+        // No random number is drawn without noise, which keeps seeded runs
+        // identical to runs without this feature
+        if self.is_enabled() && rng.gen_bool(self.probability) {
+            return match self.mode {
+                NoiseMode::RandomOpinion => Some(rng.gen_range(0..k)),
+                NoiseMode::KeepOwn => None,
+            };
+        }
+        update(rng)
+    }
+}
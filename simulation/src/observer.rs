@@ -0,0 +1,161 @@
+use std::fmt::Debug;
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    opinion_distribution::OpinionDistribution,
+    recorder::{Cadence, Schedule},
+    Model,
+};
+
+/// Read access to the state of a simulation.
+pub struct Observation<'a> {
+    /// Number of agents
+    pub n: u64,
+    /// Number of opinions
+    pub k: u16,
+    /// Number of interactions
+    pub interaction_count: u64,
+    /// Stores number of occurences for each opinion
+    pub opinion_distribution: &'a OpinionDistribution,
+}
+
+/// Observes a simulation while it runs. Observers are called once before the
+/// first interaction and after every interaction (population model) or round
+/// (gossip model).
+pub trait Observer: Debug + Send + Sync {
+    /// Name under which the collected values are stored.
+    fn name(&self) -> String;
+
+    /// Called after every interaction or round.
+    fn observe(&mut self, observation: &Observation);
+
+    /// Called once the simulation finished.
+    fn finish(&mut self, _observation: &Observation) {}
+
+    /// Returns the collected values as (interaction count, value) pairs.
+    fn values(&self) -> Vec<(u64, f64)>;
+
+    fn box_clone(&self) -> Box<dyn Observer>;
+}
+
+impl Clone for Box<dyn Observer> {
+    fn clone(&self) -> Self {
+        self.box_clone()
+    }
+}
+
+/// Metrics of the opinion distribution provided by built-in observers.
+#[derive(Clone, Debug, Deserialize, Serialize, ValueEnum, PartialEq)]
+pub enum Metric {
+    /// Shannon entropy of the opinion distribution to the base of k
+    Entropy,
+    /// Number of opinions held by at least one agent
+    SurvivingOpinions,
+    /// Difference between the largest and second largest opinion share
+    PluralityMargin,
+    /// Largest opinion share
+    MaxShare,
+}
+
+impl std::fmt::Display for Metric {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Metric::Entropy => write!(f, "entropy"),
+            Metric::SurvivingOpinions => write!(f, "surviving-opinions"),
+            Metric::PluralityMargin => write!(f, "plurality-margin"),
+            Metric::MaxShare => write!(f, "max-share"),
+        }
+    }
+}
+
+impl Metric {
+    pub fn calculate(&self, observation: &Observation) -> f64 {
+        let counts = observation.opinion_distribution.to_vec(observation.k);
+        let n = observation.n as f64;
+        match self {
+            Metric::Entropy => {
+                if observation.k < 2 {
+                    return 0.0;
+                }
+                let mut entropy = 0.0;
+                for count in counts.into_iter().filter(|count| *count > 0) {
+                    let percentage = count as f64 / n;
+                    entropy -= percentage * percentage.log(observation.k as f64);
+                }
+                entropy
+            }
+            Metric::SurvivingOpinions => counts.iter().filter(|count| **count > 0).count() as f64,
+            Metric::PluralityMargin => {
+                let (mut largest, mut second_largest) = (0, 0);
+                for count in counts {
+                    if count > largest {
+                        second_largest = largest;
+                        largest = count;
+                    } else if count > second_largest {
+                        second_largest = count;
+                    }
+                }
+                (largest - second_largest) as f64 / n
+            }
+            Metric::MaxShare => *counts.iter().max().unwrap_or(&0) as f64 / n,
+        }
+    }
+}
+
+/// Built-in observer calculating a metric once per round.
+#[derive(Clone, Debug)]
+pub struct MetricObserver {
+    metric: Metric,
+    schedule: Schedule,
+    values: Vec<(u64, f64)>,
+}
+
+impl MetricObserver {
+    pub fn new(metric: Metric, model: &Model, n: u64) -> Self {
+        MetricObserver {
+            metric,
+            schedule: Schedule::new(Cadence::Rounds, model, n),
+            values: vec![],
+        }
+    }
+}
+
+impl Observer for MetricObserver {
+    fn name(&self) -> String {
+        self.metric.to_string()
+    }
+
+    fn observe(&mut self, observation: &Observation) {
+        if self.schedule.is_due(observation.interaction_count) {
+            self.values.push((
+                observation.interaction_count,
+                self.metric.calculate(observation),
+            ));
+        }
+    }
+
+    fn finish(&mut self, observation: &Observation) {
+        // Always end with the value at the end of the simulation
+        if self
+            .values
+            .last()
+            .map(|(interaction_count, _)| *interaction_count)
+            != Some(observation.interaction_count)
+        {
+            self.values.push((
+                observation.interaction_count,
+                self.metric.calculate(observation),
+            ));
+        }
+    }
+
+    fn values(&self) -> Vec<(u64, f64)> {
+        self.values.clone()
+    }
+
+    fn box_clone(&self) -> Box<dyn Observer> {
+        Box::new(self.clone())
+    }
+}
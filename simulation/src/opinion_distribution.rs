@@ -1,8 +1,14 @@
 use std::collections::HashMap;
 
+use rand::Rng;
+
+use crate::{sampling::Sampling, tally::Tally};
+
 #[derive(Clone, Debug, Default)]
 pub struct OpinionDistribution {
     pub map: HashMap<u16, u64>,
+    /// Opinions whose support dropped to zero since the last drain
+    extinct: Vec<u16>,
 }
 
 impl OpinionDistribution {
@@ -12,7 +18,13 @@ impl OpinionDistribution {
 
     pub fn update(&mut self, old_opinion: Option<u16>, new_opinion: u16) {
         if let Some(old_opinion) = old_opinion {
+            if old_opinion == new_opinion {
+                return;
+            }
             self.map.entry(old_opinion).and_modify(|v| *v -= 1);
+            if self.get(old_opinion) == 0 {
+                self.extinct.push(old_opinion);
+            }
         }
         self.map
             .entry(new_opinion)
@@ -20,6 +32,19 @@ impl OpinionDistribution {
             .or_insert_with(|| 1);
     }
 
+    /// Adds the changes `changed` made relative to `base`, i.e. the updates
+    /// applied to a copy of `base`.
+    pub fn merge(&mut self, base: &OpinionDistribution, changed: &OpinionDistribution) {
+        for (opinion, count) in &changed.map {
+            let old_count = self.get(*opinion);
+            let new_count = old_count + count - base.get(*opinion);
+            if old_count > 0 && new_count == 0 {
+                self.extinct.push(*opinion);
+            }
+            self.map.insert(*opinion, new_count);
+        }
+    }
+
     pub fn check_occurence_with(&self, occurence_count: u64) -> bool {
         for value in self.map.values() {
             if value.eq(&occurence_count) {
@@ -28,4 +53,89 @@ impl OpinionDistribution {
         }
         false
     }
+
+    /// Returns the opinions whose support dropped to zero since the last
+    /// call and still has none.
+    pub fn drain_extinct(&mut self) -> Vec<u16> {
+        let mut extinct = std::mem::take(&mut self.extinct);
+        extinct.retain(|opinion| self.get(*opinion) == 0);
+        extinct.sort_unstable();
+        extinct.dedup();
+        extinct
+    }
+
+    /// Returns the number of agents holding the given opinion.
+    pub fn get(&self, opinion: u16) -> u64 {
+        *self.map.get(&opinion).unwrap_or(&0)
+    }
+
+    /// Returns the number of agents per opinion, indexed by opinion.
+    pub fn to_vec(&self, k: u16) -> Vec<u64> {
+        (0..k).map(|opinion| self.get(opinion)).collect()
+    }
+
+    /// Returns the opinion of an agent chosen uniformly at random out of all
+    /// `n` agents.
+    pub fn choose_opinion<R: Rng>(&self, k: u16, n: u64, rng: &mut R) -> u16 {
+        let mut index = rng.gen_range(0..n);
+        for opinion in 0..k {
+            let count = self.get(opinion);
+            if index < count {
+                return opinion;
+            }
+            index -= count;
+        }
+        unreachable!("opinion counts should sum up to n")
+    }
+
+    /// Draws the sample of an agent holding `own_opinion` out of all `n`
+    /// agents without materializing them and counts the sampled agents per
+    /// opinion into `sample`. Without replacement this is a multivariate
+    /// hypergeometric draw, with replacement a multinomial draw, both done
+    /// one agent at a time. Agents beyond the counted ones are Byzantine,
+    /// the number of sampled Byzantine agents is returned.
+    #[allow(clippy::too_many_arguments)]
+    pub fn sample<R: Rng>(
+        &self,
+        k: u16,
+        n: u64,
+        j: u8,
+        own_opinion: u16,
+        sampling: &Sampling,
+        sample: &mut Tally,
+        rng: &mut R,
+    ) -> u64 {
+        sample.clear();
+        let mut byzantine = 0;
+        let excluded = (!sampling.include_self).then_some(own_opinion);
+        let mut remaining = n - excluded.is_some() as u64;
+        let sample_size = if sampling.with_replacement {
+            j as u64
+        } else {
+            (j as u64).min(remaining)
+        };
+        for _ in 0..sample_size {
+            let mut index = rng.gen_range(0..remaining);
+            let mut sampled = None;
+            for opinion in 0..k {
+                let mut available = self.get(opinion) - excluded.eq(&Some(opinion)) as u64;
+                if !sampling.with_replacement {
+                    available -= sample.get(opinion);
+                }
+                if index < available {
+                    sampled = Some(opinion);
+                    break;
+                }
+                index -= available;
+            }
+            match sampled {
+                Some(opinion) => sample.add(opinion),
+                None => byzantine += 1,
+            }
+            if !sampling.with_replacement {
+                remaining -= 1;
+            }
+        }
+        byzantine
+    }
 }
@@ -0,0 +1,238 @@
+use std::{fmt::Debug, str::FromStr};
+
+use rand::RngCore;
+use serde::{de::Error, Deserialize, Deserializer, Serialize};
+
+use crate::{
+    agent::{choose_major_opinion, choose_sample_member},
+    tally::Tally,
+    tie_break::TieBreak,
+};
+
+/// Update rule of an agent. Opinions are numbered `0..k`, protocols may use
+/// further states, e.g. an undecided state.
+pub trait Protocol: Debug + Send + Sync {
+    /// Returns the number of states an agent can be in.
+    fn states(&self, k: u16) -> u16 {
+        k
+    }
+
+    /// Returns the new state of an agent in state `own_state`, given its
+    /// sample as number of sampled agents per state. Returns `None` if the
+    /// agent keeps its state.
+    fn update(
+        &self,
+        sample: &Tally,
+        own_state: u16,
+        k: u16,
+        tie_break: &TieBreak,
+        rng: &mut dyn RngCore,
+    ) -> Option<u16>;
+}
+
+/// Adopts the most frequent opinion of the sample.
+#[derive(Clone, Debug)]
+pub struct JMajority;
+
+impl Protocol for JMajority {
+    fn update(
+        &self,
+        sample: &Tally,
+        own_state: u16,
+        _k: u16,
+        tie_break: &TieBreak,
+        rng: &mut dyn RngCore,
+    ) -> Option<u16> {
+        choose_major_opinion(sample, own_state, tie_break, rng)
+    }
+}
+
+/// Adopts the opinion of a uniformly chosen member of the sample.
+#[derive(Clone, Debug)]
+pub struct Voter;
+
+impl Protocol for Voter {
+    fn update(
+        &self,
+        sample: &Tally,
+        _own_state: u16,
+        _k: u16,
+        _tie_break: &TieBreak,
+        rng: &mut dyn RngCore,
+    ) -> Option<u16> {
+        choose_sample_member(sample, rng)
+    }
+}
+
+/// Undecided-state dynamics. Agents meeting an agent of another opinion
+/// become undecided, undecided agents adopt the opinion of the agent they
+/// meet. The undecided state is `k`. With a sample size above one, the met
+/// agent is a uniformly chosen member of the sample.
+#[derive(Clone, Debug)]
+pub struct UndecidedState;
+
+impl Protocol for UndecidedState {
+    fn states(&self, k: u16) -> u16 {
+        k + 1
+    }
+
+    fn update(
+        &self,
+        sample: &Tally,
+        own_state: u16,
+        k: u16,
+        _tie_break: &TieBreak,
+        rng: &mut dyn RngCore,
+    ) -> Option<u16> {
+        let met_state = choose_sample_member(sample, rng)?;
+        if met_state == k || met_state == own_state {
+            None
+        } else if own_state == k {
+            Some(met_state)
+        } else {
+            Some(k)
+        }
+    }
+}
+
+/// Adopts the most frequent opinion of the sample only if at least `q`
+/// sampled agents agree on it.
+#[derive(Clone, Debug)]
+pub struct QVoter {
+    pub q: u8,
+}
+
+impl Protocol for QVoter {
+    fn update(
+        &self,
+        sample: &Tally,
+        own_state: u16,
+        _k: u16,
+        tie_break: &TieBreak,
+        rng: &mut dyn RngCore,
+    ) -> Option<u16> {
+        if sample.max() < self.q as u64 {
+            return None;
+        }
+        choose_major_opinion(sample, own_state, tie_break, rng)
+    }
+}
+
+/// Adopts the median of the own opinion and the sampled opinions. Opinions
+/// are ordered by their number, for an even count the lower median is taken.
+#[derive(Clone, Debug)]
+pub struct Median;
+
+impl Protocol for Median {
+    fn update(
+        &self,
+        sample: &Tally,
+        own_state: u16,
+        _k: u16,
+        _tie_break: &TieBreak,
+        _rng: &mut dyn RngCore,
+    ) -> Option<u16> {
+        let mut index = sample.total() / 2;
+        for (opinion, count) in sample.iter_with(own_state, 1) {
+            if index < count {
+                return Some(opinion);
+            }
+            index -= count;
+        }
+        None
+    }
+}
+
+/// Protocols selectable for a simulation.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub enum ProtocolKind {
+    /// Adopt the most frequent opinion of the sample
+    #[default]
+    JMajority,
+    /// Adopt the opinion of a random sample member
+    Voter,
+    /// Undecided-state dynamics
+    UndecidedState,
+    /// Adopt the most frequent opinion if at least q sampled agents agree
+    QVoter {
+        #[serde(deserialize_with = "positive_threshold")]
+        q: u8,
+    },
+    /// Adopt the median of the own and the sampled opinions
+    Median,
+}
+
+/// Rejects a threshold of zero, like parsing from the command line does.
+fn positive_threshold<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u8, D::Error> {
+    match u8::deserialize(deserializer)? {
+        0 => Err(D::Error::custom("q has to be a positive integer")),
+        q => Ok(q),
+    }
+}
+
+impl FromStr for ProtocolKind {
+    type Err = String;
+
+    /// Parses `j-majority`, `voter`, `undecided-state`, `q-voter:<q>` or
+    /// `median`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            None if s == "j-majority" => Ok(ProtocolKind::JMajority),
+            None if s == "voter" => Ok(ProtocolKind::Voter),
+            None if s == "undecided-state" => Ok(ProtocolKind::UndecidedState),
+            None if s == "median" => Ok(ProtocolKind::Median),
+            Some(("q-voter", q)) => match q.parse() {
+                Ok(q) if q > 0 => Ok(ProtocolKind::QVoter { q }),
+                _ => Err(String::from("q has to be a positive integer")),
+            },
+            _ => Err(String::from(
+                "expected `j-majority`, `voter`, `undecided-state`, `q-voter:<q>` or `median`",
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for ProtocolKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProtocolKind::JMajority => write!(f, "j-majority"),
+            ProtocolKind::Voter => write!(f, "voter"),
+            ProtocolKind::UndecidedState => write!(f, "undecided-state"),
+            ProtocolKind::QVoter { q } => write!(f, "q-voter:{q}"),
+            ProtocolKind::Median => write!(f, "median"),
+        }
+    }
+}
+
+impl Protocol for ProtocolKind {
+    fn states(&self, k: u16) -> u16 {
+        match self {
+            ProtocolKind::JMajority => JMajority.states(k),
+            ProtocolKind::Voter => Voter.states(k),
+            ProtocolKind::UndecidedState => UndecidedState.states(k),
+            ProtocolKind::QVoter { q } => QVoter { q: *q }.states(k),
+            ProtocolKind::Median => Median.states(k),
+        }
+    }
+
+    fn update(
+        &self,
+        sample: &Tally,
+        own_state: u16,
+        k: u16,
+        tie_break: &TieBreak,
+        rng: &mut dyn RngCore,
+    ) -> Option<u16> {
+        match self {
+            ProtocolKind::JMajority => JMajority.update(sample, own_state, k, tie_break, rng),
+            ProtocolKind::Voter => Voter.update(sample, own_state, k, tie_break, rng),
+            ProtocolKind::UndecidedState => {
+                UndecidedState.update(sample, own_state, k, tie_break, rng)
+            }
+            ProtocolKind::QVoter { q } => {
+                QVoter { q: *q }.update(sample, own_state, k, tie_break, rng)
+            }
+            ProtocolKind::Median => Median.update(sample, own_state, k, tie_break, rng),
+        }
+    }
+}
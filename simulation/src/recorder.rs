@@ -0,0 +1,146 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{error::SimulationError, opinion_distribution::OpinionDistribution, Model};
+
+/// Cadence at which snapshots of the opinion distribution are taken. All
+/// interaction counts are given in the unit of the simulations interaction
+/// count, i.e. rounds for the gossip model.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub enum Cadence {
+    /// Take a snapshot every x interactions
+    Interactions(u64),
+    /// Take a snapshot every round (n interactions in the population model)
+    Rounds,
+    /// Take snapshots at interaction counts growing by the given factor
+    Geometric(f64),
+}
+
+impl FromStr for Cadence {
+    type Err = String;
+
+    /// Parses `rounds`, `every:<x>` or `geometric:<factor>`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            None if s == "rounds" => Ok(Cadence::Rounds),
+            Some(("every", interval)) => match interval.parse::<u64>() {
+                Ok(interval) if interval > 0 => Ok(Cadence::Interactions(interval)),
+                _ => Err(String::from("interval has to be a positive integer")),
+            },
+            Some(("geometric", factor)) => match factor.parse::<f64>() {
+                Ok(factor) if factor > 1.0 => Ok(Cadence::Geometric(factor)),
+                _ => Err(String::from("factor has to be a number greater than 1")),
+            },
+            _ => Err(String::from(
+                "expected `rounds`, `every:<x>` or `geometric:<factor>`",
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for Cadence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Cadence::Interactions(interval) => write!(f, "every:{interval}"),
+            Cadence::Rounds => write!(f, "rounds"),
+            Cadence::Geometric(factor) => write!(f, "geometric:{factor}"),
+        }
+    }
+}
+
+impl Cadence {
+    /// Checks whether the cadence advances, which parsing ensures for the
+    /// command line but not for scenario files.
+    pub fn validate(&self) -> Result<(), SimulationError> {
+        match self {
+            Cadence::Interactions(0) => Err(SimulationError::InvalidCadence(String::from(
+                "interval has to be a positive integer",
+            ))),
+            Cadence::Geometric(factor) if factor.is_nan() || *factor <= 1.0 => Err(
+                SimulationError::InvalidCadence(String::from("factor has to be greater than 1")),
+            ),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Opinion distribution at a given interaction count.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Snapshot {
+    pub interaction_count: u64,
+    /// Number of agents per opinion, indexed by opinion
+    pub opinion_distribution: Vec<u64>,
+}
+
+/// Decides at which interaction counts a cadence is due.
+#[derive(Clone, Debug)]
+pub struct Schedule {
+    cadence: Cadence,
+    /// Number of interactions forming one round
+    round_length: u64,
+    /// Interaction count at which the cadence is due next
+    next: u64,
+}
+
+impl Schedule {
+    pub fn new(cadence: Cadence, model: &Model, n: u64) -> Self {
+        let round_length = match model {
+            Model::Gossip => 1,
+            Model::Population | Model::Poisson => n,
+        };
+        Schedule {
+            cadence,
+            round_length,
+            next: 0,
+        }
+    }
+
+    /// Returns whether the cadence is due at the given interaction count and
+    /// advances to the next due interaction count if so.
+    pub fn is_due(&mut self, interaction_count: u64) -> bool {
+        if interaction_count < self.next {
+            return false;
+        }
+        while self.next <= interaction_count {
+            self.next = match self.cadence {
+                Cadence::Interactions(interval) => self.next + interval,
+                Cadence::Rounds => self.next + self.round_length,
+                Cadence::Geometric(factor) => {
+                    ((self.next as f64 * factor).ceil() as u64).max(self.next + 1)
+                }
+            };
+        }
+        true
+    }
+}
+
+/// Takes snapshots of the opinion distribution whenever they are due.
+#[derive(Clone, Debug)]
+pub struct Recorder {
+    schedule: Schedule,
+}
+
+impl Recorder {
+    pub fn new(cadence: Cadence, model: &Model, n: u64) -> Self {
+        Recorder {
+            schedule: Schedule::new(cadence, model, n),
+        }
+    }
+
+    /// Returns a snapshot if one is due at the given interaction count.
+    pub fn record(
+        &mut self,
+        interaction_count: u64,
+        opinion_distribution: &OpinionDistribution,
+        k: u16,
+    ) -> Option<Snapshot> {
+        if !self.schedule.is_due(interaction_count) {
+            return None;
+        }
+        Some(Snapshot {
+            interaction_count,
+            opinion_distribution: opinion_distribution.to_vec(k),
+        })
+    }
+}
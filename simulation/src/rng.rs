@@ -0,0 +1,109 @@
+use clap::ValueEnum;
+use rand::{Error, RngCore, SeedableRng};
+use rand_chacha::{ChaCha20Rng, ChaCha8Rng};
+use rand_pcg::Pcg64;
+use rand_xoshiro::Xoshiro256PlusPlus;
+use serde::{Deserialize, Serialize};
+
+/// Algorithm of the pseudo random number generator driving a simulation.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ValueEnum, PartialEq)]
+pub enum RngAlgorithm {
+    #[default]
+    #[value(name = "chacha8")]
+    ChaCha8,
+    #[value(name = "chacha20")]
+    ChaCha20,
+    Pcg64,
+    #[value(name = "xoshiro256pp")]
+    Xoshiro256PlusPlus,
+}
+
+impl std::fmt::Display for RngAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RngAlgorithm::ChaCha8 => write!(f, "chacha8"),
+            RngAlgorithm::ChaCha20 => write!(f, "chacha20"),
+            RngAlgorithm::Pcg64 => write!(f, "pcg64"),
+            RngAlgorithm::Xoshiro256PlusPlus => write!(f, "xoshiro256pp"),
+        }
+    }
+}
+
+/// Seeded random number generator of one of the supported algorithms.
+#[derive(Clone, Debug)]
+pub enum SimulationRng {
+    ChaCha8(ChaCha8Rng),
+    ChaCha20(ChaCha20Rng),
+    Pcg64(Pcg64),
+    Xoshiro256PlusPlus(Xoshiro256PlusPlus),
+}
+
+impl SimulationRng {
+    pub fn new(algorithm: &RngAlgorithm, seed: u64) -> Self {
+        match algorithm {
+            RngAlgorithm::ChaCha8 => SimulationRng::ChaCha8(ChaCha8Rng::seed_from_u64(seed)),
+            RngAlgorithm::ChaCha20 => SimulationRng::ChaCha20(ChaCha20Rng::seed_from_u64(seed)),
+            RngAlgorithm::Pcg64 => SimulationRng::Pcg64(Pcg64::seed_from_u64(seed)),
+            RngAlgorithm::Xoshiro256PlusPlus => {
+                SimulationRng::Xoshiro256PlusPlus(Xoshiro256PlusPlus::seed_from_u64(seed))
+            }
+        }
+    }
+}
+
+impl RngCore for SimulationRng {
+    fn next_u32(&mut self) -> u32 {
+        match self {
+            SimulationRng::ChaCha8(rng) => rng.next_u32(),
+            SimulationRng::ChaCha20(rng) => rng.next_u32(),
+            SimulationRng::Pcg64(rng) => rng.next_u32(),
+            SimulationRng::Xoshiro256PlusPlus(rng) => rng.next_u32(),
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        match self {
+            SimulationRng::ChaCha8(rng) => rng.next_u64(),
+            SimulationRng::ChaCha20(rng) => rng.next_u64(),
+            SimulationRng::Pcg64(rng) => rng.next_u64(),
+            SimulationRng::Xoshiro256PlusPlus(rng) => rng.next_u64(),
+        }
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        match self {
+            SimulationRng::ChaCha8(rng) => rng.fill_bytes(dest),
+            SimulationRng::ChaCha20(rng) => rng.fill_bytes(dest),
+            SimulationRng::Pcg64(rng) => rng.fill_bytes(dest),
+            SimulationRng::Xoshiro256PlusPlus(rng) => rng.fill_bytes(dest),
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        match self {
+            SimulationRng::ChaCha8(rng) => rng.try_fill_bytes(dest),
+            SimulationRng::ChaCha20(rng) => rng.try_fill_bytes(dest),
+            SimulationRng::Pcg64(rng) => rng.try_fill_bytes(dest),
+            SimulationRng::Xoshiro256PlusPlus(rng) => rng.try_fill_bytes(dest),
+        }
+    }
+}
+
+/// Derives a new seed from a seed and a stream index via the SplitMix64
+/// finalizer. Different streams yield statistically independent seeds.
+pub fn derive_seed(seed: u64, stream: u64) -> u64 {
+    let mut z = seed ^ stream.wrapping_add(1).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Stream of the seed from which the seeds of config generators are derived.
+/// The seeds of configs use the streams counting up from zero.
+const GENERATOR_STREAM: u64 = u64::MAX;
+
+/// Derives the seed of the `index`-th generation of initial configurations
+/// from the seed of a run of the runner.
+pub fn generator_seed(seed: u64, index: u64) -> u64 {
+    derive_seed(derive_seed(seed, GENERATOR_STREAM), index)
+}
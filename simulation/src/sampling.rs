@@ -0,0 +1,121 @@
+use rand::{seq::index, Rng};
+use serde::{Deserialize, Serialize};
+
+use crate::Model;
+
+/// Largest sample drawn without allocation when sampling without replacement.
+const FLOYD_MAX_SAMPLE: usize = 11;
+
+/// Defines how an agent draws its sample. By default, an agent samples
+/// distinct agents, see [`Sampling::default_for`] for whether it may sample
+/// itself.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+pub struct Sampling {
+    /// Whether an agent can be sampled multiple times
+    pub with_replacement: bool,
+    /// Whether the sampling agent itself can be sampled
+    pub include_self: bool,
+}
+
+impl std::fmt::Display for Sampling {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let replacement = if self.with_replacement {
+            "with"
+        } else {
+            "without"
+        };
+        let own = if self.include_self {
+            "including"
+        } else {
+            "excluding"
+        };
+        write!(f, "{replacement} replacement, {own} self")
+    }
+}
+
+impl Sampling {
+    /// Returns the sampling of simulations which do not set one, as in
+    /// versions without explicit sampling modes: agents of the gossip model
+    /// may sample themselves, agents of the other models may not.
+    pub fn default_for(model: &Model) -> Self {
+        Sampling {
+            with_replacement: false,
+            include_self: *model == Model::Gossip,
+        }
+    }
+
+    /// Writes `j` positions out of `population` positions into `positions`.
+    fn draw_positions<R: Rng>(
+        &self,
+        population: usize,
+        j: usize,
+        positions: &mut Vec<usize>,
+        rng: &mut R,
+    ) {
+        positions.clear();
+        if population == 0 {
+            return;
+        }
+        if self.with_replacement {
+            positions.extend((0..j).map(|_| rng.gen_range(0..population)));
+        } else if j <= FLOYD_MAX_SAMPLE && population <= u32::MAX as usize {
+            // Floyd's algorithm, which `index::sample` uses for small samples
+            // as well. Drawing the same positions keeps seeded runs unchanged.
+            let population = population as u32;
+            for bound in population - (j as u32).min(population)..population {
+                let position = rng.gen_range(0..=bound) as usize;
+                if positions.contains(&position) {
+                    positions.push(bound as usize);
+                } else {
+                    positions.push(position);
+                }
+            }
+        } else {
+            positions.extend(index::sample(rng, population, j.min(population)).iter());
+        }
+    }
+
+    /// Writes the indices of `j` sampled agents out of `n` agents for the
+    /// agent at index `chosen` into `indices`.
+    pub fn draw_indices<R: Rng>(
+        &self,
+        n: usize,
+        j: usize,
+        chosen: usize,
+        indices: &mut Vec<usize>,
+        rng: &mut R,
+    ) {
+        let population = if self.include_self { n } else { n - 1 };
+        self.draw_positions(population, j, indices, rng);
+        if self.include_self {
+            return;
+        }
+        // Skip over the chosen agent
+        for index in indices.iter_mut() {
+            if *index >= chosen {
+                *index += 1;
+            }
+        }
+    }
+
+    /// Writes the nodes of `j` sampled agents out of the neighbors of the
+    /// agent at node `chosen` into `indices`.
+    pub fn draw_neighbors<R: Rng>(
+        &self,
+        neighbors: &[u32],
+        j: usize,
+        chosen: usize,
+        indices: &mut Vec<usize>,
+        rng: &mut R,
+    ) {
+        let population = neighbors.len() + self.include_self as usize;
+        self.draw_positions(population, j, indices, rng);
+        for index in indices.iter_mut() {
+            *index = match neighbors.get(*index) {
+                Some(neighbor) => *neighbor as usize,
+                None => chosen,
+            };
+        }
+    }
+}
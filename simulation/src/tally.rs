@@ -0,0 +1,91 @@
+/// Number of sampled agents per state, stored in an array indexed by state.
+/// A tally is cleared and reused for every interaction, so counting a sample
+/// allocates nothing once the array covers all states.
+#[derive(Clone, Debug, Default)]
+pub struct Tally {
+    /// Count per state, indexed by state
+    counts: Vec<u64>,
+    /// States with a positive count in ascending order
+    states: Vec<u16>,
+    /// Sum of all counts
+    total: u64,
+}
+
+impl Tally {
+    /// Removes all counts in time linear in the number of counted states.
+    pub fn clear(&mut self) {
+        for state in self.states.drain(..) {
+            self.counts[state as usize] = 0;
+        }
+        self.total = 0;
+    }
+
+    /// Counts a single agent in the given state.
+    pub fn add(&mut self, state: u16) {
+        self.add_many(state, 1);
+    }
+
+    /// Counts `count` agents in the given state.
+    pub fn add_many(&mut self, state: u16, count: u64) {
+        if count == 0 {
+            return;
+        }
+        if state as usize >= self.counts.len() {
+            self.counts.resize(state as usize + 1, 0);
+        }
+        if self.counts[state as usize] == 0 {
+            let position = self.states.partition_point(|other| *other < state);
+            self.states.insert(position, state);
+        }
+        self.counts[state as usize] += count;
+        self.total += count;
+    }
+
+    /// Returns the number of agents counted in the given state.
+    pub fn get(&self, state: u16) -> u64 {
+        self.counts.get(state as usize).copied().unwrap_or(0)
+    }
+
+    /// Returns the number of counted agents.
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Returns the largest count of a state.
+    pub fn max(&self) -> u64 {
+        self.iter().map(|(_, count)| count).max().unwrap_or(0)
+    }
+
+    /// Returns the states with a positive count together with their count in
+    /// ascending order of the states.
+    pub fn iter(&self) -> impl Iterator<Item = (u16, u64)> + Clone + '_ {
+        self.states
+            .iter()
+            .map(|state| (*state, self.counts[*state as usize]))
+    }
+
+    /// Returns the states like `iter`, with `extra` more agents counted in
+    /// the given state.
+    pub fn iter_with(
+        &self,
+        state: u16,
+        extra: u64,
+    ) -> impl Iterator<Item = (u16, u64)> + Clone + '_ {
+        let missing = extra > 0 && self.get(state) == 0;
+        self.iter()
+            .filter(move |(other, _)| *other < state)
+            .chain(missing.then_some((state, 0)))
+            .chain(self.iter().filter(move |(other, _)| *other >= state))
+            .map(move |(other, count)| (other, count + if other == state { extra } else { 0 }))
+    }
+}
+
+impl<const N: usize> From<[(u16, u64); N]> for Tally {
+    fn from(counts: [(u16, u64); N]) -> Self {
+        let mut tally = Tally::default();
+        for (state, count) in counts {
+            tally.add_many(state, count);
+        }
+        tally
+    }
+}
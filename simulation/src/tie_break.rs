@@ -0,0 +1,31 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+/// Rule deciding the new opinion of an agent if several opinions occur
+/// equally often in its sample.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ValueEnum, PartialEq)]
+pub enum TieBreak {
+    /// Choose one of the tied opinions uniformly at random
+    #[default]
+    Uniform,
+    /// Keep the own opinion
+    KeepOwn,
+    /// Count the own opinion as an additional vote, then choose uniformly
+    SelfVote,
+    /// Choose the tied opinion with the smallest id
+    SmallestOpinion,
+    /// Adopt the opinion of a uniformly chosen sample member
+    RandomSampleMember,
+}
+
+impl std::fmt::Display for TieBreak {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TieBreak::Uniform => write!(f, "uniform"),
+            TieBreak::KeepOwn => write!(f, "keep-own"),
+            TieBreak::SelfVote => write!(f, "self-vote"),
+            TieBreak::SmallestOpinion => write!(f, "smallest-opinion"),
+            TieBreak::RandomSampleMember => write!(f, "random-sample-member"),
+        }
+    }
+}
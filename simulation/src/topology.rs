@@ -0,0 +1,184 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::SimulationError;
+
+/// Graph on which the agents interact. Agents sample only among their
+/// neighbors.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub enum Topology {
+    /// Every agent is connected to every other agent
+    #[default]
+    Complete,
+    /// Ring where every agent is connected to its `degree` nearest agents
+    Ring { degree: u32 },
+    /// Two dimensional grid with `width` columns wrapping around at the
+    /// borders, every agent is connected to its four direct neighbors
+    Torus { width: u64 },
+    /// Every pair of agents is connected with the given probability
+    ErdosRenyi { probability: f64 },
+    /// Uniformly chosen graph where every agent has the given degree
+    RandomRegular { degree: u32 },
+    /// Preferential attachment graph where every new agent connects to
+    /// `attachments` existing agents
+    BarabasiAlbert { attachments: u32 },
+    /// Agents are split into equally sized blocks. Agents of the same block
+    /// are connected with probability `p_in`, others with probability `p_out`
+    StochasticBlockModel { blocks: u32, p_in: f64, p_out: f64 },
+    /// Graph loaded from a file with one edge per line
+    EdgeList { path: String },
+}
+
+impl FromStr for Topology {
+    type Err = String;
+
+    /// Parses `complete`, `ring:<degree>`, `torus:<width>`,
+    /// `erdos-renyi:<probability>`, `random-regular:<degree>`,
+    /// `barabasi-albert:<attachments>`, `sbm:<blocks>:<p_in>:<p_out>` or
+    /// `edge-list:<path>`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, parameters) = s.split_once(':').unwrap_or((s, ""));
+        // Paths may contain colons themselves
+        if kind == "edge-list" && !parameters.is_empty() {
+            return Ok(Topology::EdgeList {
+                path: parameters.to_string(),
+            });
+        }
+        let parameters = parameters.split(':').collect::<Vec<_>>();
+        let parse_error = |parameter: &str| format!("invalid {parameter} for topology {kind}");
+        match (kind, parameters.as_slice()) {
+            ("complete", [""]) => Ok(Topology::Complete),
+            ("ring", [degree]) => Ok(Topology::Ring {
+                degree: degree.parse().map_err(|_| parse_error("degree"))?,
+            }),
+            ("torus", [width]) => Ok(Topology::Torus {
+                width: width.parse().map_err(|_| parse_error("width"))?,
+            }),
+            ("erdos-renyi", [probability]) => Ok(Topology::ErdosRenyi {
+                probability: probability
+                    .parse()
+                    .map_err(|_| parse_error("probability"))?,
+            }),
+            ("random-regular", [degree]) => Ok(Topology::RandomRegular {
+                degree: degree.parse().map_err(|_| parse_error("degree"))?,
+            }),
+            ("barabasi-albert", [attachments]) => Ok(Topology::BarabasiAlbert {
+                attachments: attachments
+                    .parse()
+                    .map_err(|_| parse_error("attachments"))?,
+            }),
+            ("sbm", [blocks, p_in, p_out]) => Ok(Topology::StochasticBlockModel {
+                blocks: blocks.parse().map_err(|_| parse_error("blocks"))?,
+                p_in: p_in.parse().map_err(|_| parse_error("p_in"))?,
+                p_out: p_out.parse().map_err(|_| parse_error("p_out"))?,
+            }),
+            _ => Err(String::from(
+                "expected `complete`, `ring:<degree>`, `torus:<width>`, \
+                `erdos-renyi:<probability>`, `random-regular:<degree>`, \
+                `barabasi-albert:<attachments>`, `sbm:<blocks>:<p_in>:<p_out>` \
+                or `edge-list:<path>`",
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for Topology {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Topology::Complete => write!(f, "complete"),
+            Topology::Ring { degree } => write!(f, "ring:{degree}"),
+            Topology::Torus { width } => write!(f, "torus:{width}"),
+            Topology::ErdosRenyi { probability } => write!(f, "erdos-renyi:{probability}"),
+            Topology::RandomRegular { degree } => write!(f, "random-regular:{degree}"),
+            Topology::BarabasiAlbert { attachments } => {
+                write!(f, "barabasi-albert:{attachments}")
+            }
+            Topology::StochasticBlockModel {
+                blocks,
+                p_in,
+                p_out,
+            } => write!(f, "sbm:{blocks}:{p_in}:{p_out}"),
+            Topology::EdgeList { path } => write!(f, "edge-list:{path}"),
+        }
+    }
+}
+
+impl Topology {
+    /// Returns the number of neighbors of every agent if it is the same for
+    /// all agents.
+    pub fn degree(&self) -> Option<u64> {
+        match self {
+            Topology::Ring { degree } | Topology::RandomRegular { degree } => Some(*degree as u64),
+            Topology::Torus { .. } => Some(4),
+            _ => None,
+        }
+    }
+
+    /// Returns whether graphs of this topology may be disconnected, in which
+    /// case consensus may never be reached.
+    pub fn may_be_disconnected(&self) -> bool {
+        matches!(
+            self,
+            Topology::ErdosRenyi { .. }
+                | Topology::RandomRegular { .. }
+                | Topology::StochasticBlockModel { .. }
+        )
+    }
+
+    /// Checks whether a graph of this topology can be built for `n` agents.
+    pub fn validate(&self, n: u64) -> Result<(), SimulationError> {
+        let invalid = |message: &str| Err(SimulationError::InvalidTopology(message.to_string()));
+        if *self != Topology::Complete && n > u32::MAX as u64 {
+            return invalid("graphs support at most 2^32 - 1 agents");
+        }
+        match self {
+            Topology::Complete | Topology::EdgeList { .. } => Ok(()),
+            Topology::Ring { degree } => {
+                if degree % 2 != 0 || *degree == 0 || *degree as u64 >= n {
+                    return invalid("ring degree has to be even, positive and smaller than n");
+                }
+                Ok(())
+            }
+            Topology::Torus { width } => {
+                if *width == 0 || !n.is_multiple_of(*width) {
+                    return invalid("torus width has to divide n");
+                }
+                Ok(())
+            }
+            Topology::ErdosRenyi { probability } => {
+                if !(*probability > 0.0 && *probability <= 1.0) {
+                    return invalid("edge probability has to be in (0, 1]");
+                }
+                Ok(())
+            }
+            Topology::RandomRegular { degree } => {
+                if *degree == 0 || *degree as u64 >= n || !(n * *degree as u64).is_multiple_of(2) {
+                    return invalid(
+                        "degree has to be positive, smaller than n and n * degree even",
+                    );
+                }
+                Ok(())
+            }
+            Topology::BarabasiAlbert { attachments } => {
+                if *attachments == 0 || *attachments as u64 >= n {
+                    return invalid("attachments have to be positive and smaller than n");
+                }
+                Ok(())
+            }
+            Topology::StochasticBlockModel {
+                blocks,
+                p_in,
+                p_out,
+            } => {
+                if *blocks == 0 || *blocks as u64 > n {
+                    return invalid("number of blocks has to be positive and at most n");
+                }
+                if !(0.0..=1.0).contains(p_in) || !(0.0..=1.0).contains(p_out) {
+                    return invalid("edge probabilities have to be in [0, 1]");
+                }
+                Ok(())
+            }
+        }
+    }
+}
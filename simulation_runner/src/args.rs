@@ -1,48 +1,205 @@
+use std::num::NonZeroUsize;
+
 use anyhow::Result;
-use clap::{CommandFactory, Parser};
+use clap::{ArgGroup, CommandFactory, Parser};
 use clap_verbosity_flag::Verbosity;
-use simulation::{config::Config, Model};
+use simulation::{
+    adversary::{Adversary, Strategy},
+    config::Config,
+    error::SimulationError,
+    generator::ConfigGenerator,
+    noise::{Noise, NoiseMode},
+    observer::Metric,
+    protocol::ProtocolKind,
+    recorder::Cadence,
+    rng::{derive_seed, generator_seed, RngAlgorithm},
+    sampling::Sampling,
+    tie_break::TieBreak,
+    topology::Topology,
+    Engine, Model,
+};
 
 #[derive(Clone, Parser)]
+#[command(group(ArgGroup::new("config").multiple(true)))]
 pub struct Args {
+    /// Run the simulation described by a TOML or JSON scenario file
+    ///
+    /// Replaces all flags describing the simulations. The resolved scenario
+    /// is stored next to the simulations.
+    #[arg(long, conflicts_with = "config")]
+    pub scenario: Option<String>,
+    /// Run all simulations described by a TOML or JSON sweep file
+    ///
+    /// Replaces all flags describing the simulations. The expanded configs
+    /// are stored next to the simulations.
+    #[arg(long, conflicts_with_all = ["config", "scenario"])]
+    pub sweep: Option<String>,
     /// Number of agents
-    #[arg(short, long, default_value_t = 100000)]
+    #[arg(short, long, group = "config", default_value_t = 100000)]
     pub n: u64,
     /// Upper treshold for n
     ///
     /// Set to simulate for all [n, total_n]
-    #[arg(long)]
+    #[arg(long, group = "config")]
     pub total_n: Option<u64>,
-    #[arg(long, default_value_t = 1000)]
+    #[arg(long, group = "config", default_value_t = 1000)]
     pub n_step_size: u64,
     /// Sample size
-    #[arg(short, long, default_value_t = 3)]
+    #[arg(short, long, group = "config", default_value_t = 3)]
     pub j: u8,
     /// Upper treshold for j
     ///
     /// Set to simulate for all [j, total_j]
-    #[arg(long)]
+    #[arg(long, group = "config")]
     pub total_j: Option<u8>,
-    #[arg(long, default_value_t = 1)]
+    #[arg(long, group = "config", default_value_t = 1)]
     pub j_step_size: u8,
     /// Number of opinions
-    #[arg(short, long, default_value_t = 2)]
+    #[arg(short, long, group = "config", default_value_t = 2)]
     pub k: u16,
     /// Upper treshold for k
     ///
     /// Set to simulate for all [k, total_k]
-    #[arg(long)]
+    #[arg(long, group = "config")]
     pub total_k: Option<u16>,
-    #[arg(long, default_value_t = 1)]
+    #[arg(long, group = "config", default_value_t = 1)]
     pub k_step_size: u16,
     /// Initial consensus configuration
-    #[arg(long, use_value_delimiter = true)]
+    ///
+    /// Covers the honest agents only, so it should sum up to n minus the
+    /// adversary size
+    #[arg(long, group = "config", use_value_delimiter = true)]
     pub initial_config: Option<Vec<u64>>,
+    /// Generator of initial configurations
+    ///
+    /// Either `lattice:<step>` for all configurations on a lattice with the
+    /// given step, `additive-bias:<bias>`, `multiplicative-bias:<factor>`,
+    /// `zipf:<exponent>`, `dirichlet:<alpha>` or `csv:<path>` for a file with
+    /// one configuration per line. Biased configurations favor opinion 0
+    #[arg(long, group = "config", conflicts_with = "initial_config")]
+    pub config_generator: Option<ConfigGenerator>,
+    /// Number of stubborn agents per opinion
+    ///
+    /// Stubborn agents are part of the initial configuration and never
+    /// change their opinion
+    #[arg(long, group = "config", use_value_delimiter = true)]
+    pub stubborn_config: Option<Vec<u64>>,
+    /// Share of agents which have to agree on an opinion to reach consensus
+    ///
+    /// If not set, all non-stubborn agents have to agree
+    #[arg(long, group = "config")]
+    pub consensus_fraction: Option<f64>,
     /// Number of simulations to run
     #[arg(long, default_value_t = 10)]
     pub batch_size: usize,
+    /// Number of simulations running at the same time
+    ///
+    /// Defaults to the number of cores. All simulations of all
+    /// configurations share these worker threads
+    #[arg(long)]
+    pub jobs: Option<NonZeroUsize>,
+    #[arg(long, group = "config", required_unless_present_any = ["scenario", "sweep"])]
+    pub model: Option<Model>,
+    /// Engine storing the state of the agents
+    ///
+    /// The count engine only stores the number of agents per opinion and
+    /// scales to very large n
+    #[arg(long, group = "config", default_value_t = Engine::Agent)]
+    pub engine: Engine,
+    /// Compute each round of the gossip model on all cores
+    ///
+    /// Speeds up single runs with large n, requires the agent engine
+    #[arg(long, group = "config", default_value_t = false)]
+    pub parallel_rounds: bool,
+    /// Seed from which the seeds of all simulations are derived
+    ///
+    /// A random seed is chosen if none is supplied
+    #[arg(long, group = "config")]
+    pub seed: Option<u64>,
+    /// Run every simulation with exactly this seed
+    ///
+    /// Pass the seed stored with a simulation to replay it. Generated initial
+    /// configurations additionally need the `--seed` of the original run
     #[arg(long)]
-    pub model: Model,
+    pub replay: Option<u64>,
+    /// Algorithm of the random number generator
+    #[arg(long, group = "config", default_value_t = RngAlgorithm::ChaCha8)]
+    pub rng: RngAlgorithm,
+    /// Record the opinion distribution during each simulation
+    ///
+    /// Either `rounds`, `every:<x>` for every x interactions or
+    /// `geometric:<factor>` for interaction counts growing by factor
+    #[arg(long, group = "config")]
+    pub record: Option<Cadence>,
+    /// Metrics to observe once per round during each simulation
+    #[arg(long, group = "config", value_enum, use_value_delimiter = true)]
+    pub observe: Vec<Metric>,
+    /// Record when some opinion first reaches these shares of the agents
+    ///
+    /// Also records when the number of surviving opinions first drops to
+    /// k - 1, ..., 1
+    #[arg(long, group = "config", use_value_delimiter = true)]
+    pub hitting_times: Vec<f64>,
+    /// Maximum number of interactions per simulation
+    ///
+    /// Simulations exceeding it are stopped and marked as censored
+    #[arg(long, group = "config")]
+    pub max_interactions: Option<u64>,
+    /// Update rule of the agents
+    ///
+    /// Either `j-majority`, `voter`, `undecided-state`, `q-voter:<q>` or
+    /// `median`
+    #[arg(long, group = "config", default_value_t = ProtocolKind::JMajority)]
+    pub protocol: ProtocolKind,
+    /// Probability that an updating agent ignores its sample
+    ///
+    /// Requires --consensus-fraction or --max-interactions, as consensus may
+    /// never be reached
+    #[arg(long, group = "config", default_value_t = 0.0)]
+    pub noise: f64,
+    /// What an agent ignoring its sample does instead
+    #[arg(long, group = "config", default_value_t = NoiseMode::RandomOpinion)]
+    pub noise_mode: NoiseMode,
+    /// Number of Byzantine agents, part of the n agents
+    #[arg(long, group = "config", default_value_t = 0)]
+    pub adversary_size: u64,
+    /// Opinion reported by a sampled Byzantine agent
+    ///
+    /// Either `runner-up`, `fixed:<opinion>`, `maximize-ties` or `random`
+    #[arg(long, group = "config", default_value_t = Strategy::RunnerUp)]
+    pub adversary_strategy: Strategy,
+    /// Rules deciding the new opinion on a tie
+    ///
+    /// Set multiple rules to simulate all of them
+    #[arg(
+        long,
+        group = "config",
+        value_enum,
+        use_value_delimiter = true,
+        default_value = "uniform"
+    )]
+    pub tie_break: Vec<TieBreak>,
+    /// Sample agents with replacement
+    #[arg(long, group = "config", default_value_t = false)]
+    pub with_replacement: bool,
+    /// Allow agents to sample themselves
+    ///
+    /// Defaults to `true` in the gossip model and `false` otherwise
+    #[arg(
+        long,
+        group = "config",
+        num_args = 0..=1,
+        default_missing_value = "true"
+    )]
+    pub include_self: Option<bool>,
+    /// Graph on which the agents interact
+    ///
+    /// Either `complete`, `ring:<degree>`, `torus:<width>`,
+    /// `erdos-renyi:<probability>`, `random-regular:<degree>`,
+    /// `barabasi-albert:<attachments>`, `sbm:<blocks>:<p_in>:<p_out>` or
+    /// `edge-list:<path>`
+    #[arg(long, group = "config", default_value_t = Topology::Complete)]
+    pub topology: Topology,
     /// Folder to store files
     #[arg(short, long)]
     pub output: String,
@@ -51,6 +208,57 @@ pub struct Args {
     pub verbose: Verbosity,
 }
 
+/// # Get simulation configs
+///
+/// Converts parameters into valid configs for all combinations of the
+/// supplied n, k, j and tie-break rules. The seed of each config is derived
+/// from the supplied seed.
+pub fn get_simulation_configs(seed: u64, args: &Args) -> Result<Vec<Config>> {
+    let total_n = args.total_n.unwrap_or(args.n);
+    let total_k = args.total_k.unwrap_or(args.k);
+    let total_j = args.total_j.unwrap_or(args.j);
+
+    let mut configs = vec![];
+    let mut generations = 0;
+    let mut n = args.n;
+    while n <= total_n {
+        let mut k = args.k;
+        while k <= total_k {
+            // All sample sizes and tie-break rules share the initial
+            // configurations
+            let honest_agents = n.saturating_sub(args.adversary_size);
+            let initial_configs = match &args.config_generator {
+                Some(generator) => generator
+                    .generate(honest_agents, k, generator_seed(seed, generations))
+                    .unwrap_or_else(|error| exit_with(&error)),
+                None => vec![initial_config(&args.initial_config, honest_agents, k)],
+            };
+            generations += 1;
+            let mut j = args.j;
+            while j <= total_j {
+                for tie_break in args.tie_break.iter() {
+                    for initial_config in initial_configs.iter() {
+                        let config_seed = derive_seed(seed, configs.len() as u64);
+                        configs.push(get_simulation_config(
+                            n,
+                            j,
+                            k,
+                            initial_config.clone(),
+                            tie_break,
+                            config_seed,
+                            args,
+                        )?);
+                    }
+                }
+                j += args.j_step_size;
+            }
+            k += args.k_step_size;
+        }
+        n += args.n_step_size;
+    }
+    Ok(configs)
+}
+
 /// # Get simulation config
 ///
 /// Converts parameters into a valid config for the simulation.
@@ -58,52 +266,164 @@ pub fn get_simulation_config(
     n: u64,
     j: u8,
     k: u16,
-    initial_config: &Option<Vec<u64>>,
-    model: Model,
+    initial_config: Vec<u64>,
+    tie_break: &TieBreak,
+    seed: u64,
+    args: &Args,
 ) -> Result<Config> {
-    let config = validate_initial_config(initial_config, n, k)?;
-    Ok(Config {
+    let model = args
+        .model
+        .clone()
+        .expect("model is required without scenario or sweep");
+    let sampling = Sampling {
+        with_replacement: args.with_replacement,
+        include_self: args
+            .include_self
+            .unwrap_or(Sampling::default_for(&model).include_self),
+    };
+    let config = Config {
         n,
         j,
         k,
-        config,
+        config: initial_config,
+        config_generator: args.config_generator.clone(),
         model,
-    })
+        engine: args.engine.clone(),
+        parallel_rounds: args.parallel_rounds,
+        seed,
+        rng: args.rng.clone(),
+        recording: args.record.clone(),
+        metrics: args.observe.clone(),
+        thresholds: args.hitting_times.clone(),
+        max_interactions: args.max_interactions,
+        protocol: args.protocol.clone(),
+        noise: Noise {
+            probability: args.noise,
+            mode: args.noise_mode.clone(),
+        },
+        adversary: Adversary {
+            size: args.adversary_size,
+            strategy: args.adversary_strategy.clone(),
+        },
+        tie_break: tie_break.clone(),
+        sampling: Some(sampling),
+        topology: args.topology.clone(),
+        stubborn: args.stubborn_config.clone().unwrap_or_default(),
+        consensus_fraction: args.consensus_fraction,
+    };
+    if let Err(error) = config.validate() {
+        exit_with(&error);
+    }
+    Ok(config)
 }
 
-/// # Validate initial config
+/// # Initial config
 ///
-/// Validates the user supplied initial config and responds with fitting error
-/// messages. If there is no initial config this function provides one.
-fn validate_initial_config(initial_config: &Option<Vec<u64>>, n: u64, k: u16) -> Result<Vec<u64>> {
+/// Returns the user supplied initial config. If there is no initial config
+/// this function provides an evenly spreaded one.
+pub fn initial_config(initial_config: &Option<Vec<u64>>, n: u64, k: u16) -> Vec<u64> {
     if let Some(initial_config) = initial_config {
-        let mut cmd = Args::command();
-        if !initial_config.len().eq(&(k as usize)) {
-            cmd.error(
-                clap::error::ErrorKind::TooFewValues,
-                "Initial initial_configuration should have k elements",
-            )
-            .exit();
-        }
-        if initial_config.iter().sum::<u64>().eq(&n) {
-            return Ok(initial_config.clone());
-        } else {
-            cmd.error(
-                clap::error::ErrorKind::ValueValidation,
-                "Initial configuration should sum up to n",
-            )
-            .exit();
-        }
+        return initial_config.clone();
     }
 
-    // If no initial config was supplied, create an evenly spreaded one
-    let initial_value = n / k as u64;
-    let remainder = n % k as u64;
+    let initial_value = n / k.max(1) as u64;
+    let remainder = n % k.max(1) as u64;
 
     let mut initial_config = vec![initial_value; k as usize];
     for item in initial_config.iter_mut().take(remainder as usize) {
         *item += 1;
     }
 
-    Ok(initial_config)
+    initial_config
+}
+
+/// # Exit with
+///
+/// Responds to an invalid config with an error message naming the flags to
+/// fix and exits.
+pub fn exit_with(error: &SimulationError) -> ! {
+    use clap::error::ErrorKind;
+
+    let (kind, message) = match error {
+        SimulationError::ConfigLength { .. } => (
+            ErrorKind::TooFewValues,
+            String::from("Initial configuration should have k elements"),
+        ),
+        SimulationError::ConfigSum { .. } => (
+            ErrorKind::ValueValidation,
+            String::from("Initial configuration should sum up to n minus the adversary size"),
+        ),
+        SimulationError::SampleSize { max, .. } => (
+            ErrorKind::ValueValidation,
+            format!("Sample size j should be between 1 and {max}"),
+        ),
+        SimulationError::QVoterThreshold { .. } => (
+            ErrorKind::ArgumentConflict,
+            String::from("Threshold q of --protocol q-voter should not exceed the sample size j"),
+        ),
+        SimulationError::InvalidTopology(message) => (
+            ErrorKind::ValueValidation,
+            format!("Invalid --topology: {message}"),
+        ),
+        SimulationError::TopologyWithoutStop => (
+            ErrorKind::MissingRequiredArgument,
+            String::from("Random topologies require --consensus-fraction or --max-interactions"),
+        ),
+        SimulationError::CountEngineTopology => (
+            ErrorKind::ArgumentConflict,
+            String::from("--engine count only supports --topology complete"),
+        ),
+        SimulationError::ParallelRounds => (
+            ErrorKind::ArgumentConflict,
+            String::from("--parallel-rounds requires --model gossip and --engine agent"),
+        ),
+        SimulationError::StubbornLength { .. } => (
+            ErrorKind::TooFewValues,
+            String::from("Stubborn configuration should have k elements"),
+        ),
+        SimulationError::StubbornAgents => (
+            ErrorKind::ValueValidation,
+            String::from("Stubborn configuration should not exceed the initial configuration"),
+        ),
+        SimulationError::ConsensusFraction(_) => (
+            ErrorKind::ValueValidation,
+            String::from("Consensus fraction should be in (0, 1]"),
+        ),
+        SimulationError::NoiseProbability(_) => (
+            ErrorKind::ValueValidation,
+            String::from("Noise should be in [0, 1]"),
+        ),
+        SimulationError::NoiseWithoutStop => (
+            ErrorKind::MissingRequiredArgument,
+            String::from("Noise requires --consensus-fraction or --max-interactions"),
+        ),
+        SimulationError::AdversarySize { .. } => (
+            ErrorKind::ValueValidation,
+            String::from("Adversary size should be smaller than n"),
+        ),
+        SimulationError::FixedOpinion { .. } => (
+            ErrorKind::ValueValidation,
+            String::from("Fixed opinion of the adversary should be smaller than k"),
+        ),
+        SimulationError::InvalidCadence(message) => (
+            ErrorKind::ValueValidation,
+            format!("Invalid --record: {message}"),
+        ),
+        SimulationError::HittingTimeThreshold(_) => (
+            ErrorKind::ValueValidation,
+            String::from("Hitting time thresholds should be in (0, 1]"),
+        ),
+        SimulationError::InvalidGenerator(message) => (
+            ErrorKind::ValueValidation,
+            format!("Invalid --config-generator: {message}"),
+        ),
+        SimulationError::ConfigFile(error) => (
+            ErrorKind::Io,
+            format!("Could not read initial configurations: {error}"),
+        ),
+        SimulationError::EdgeList(error) => {
+            (ErrorKind::Io, format!("Could not read edge list: {error}"))
+        }
+    };
+    Args::command().error(kind, message).exit()
 }
@@ -0,0 +1,171 @@
+use std::collections::BTreeMap;
+
+use anyhow::{bail, Result};
+
+/// # Evaluate
+///
+/// Evaluates an arithmetic expression like `floor(n / 3) + 1`. Supports
+/// numbers, the variables given, `+`, `-`, `*`, `/`, `%`, `^`, parentheses
+/// and the functions `floor`, `ceil`, `round`, `sqrt`, `ln`, `log2`, `min`
+/// and `max`.
+pub fn evaluate(expression: &str, variables: &BTreeMap<String, f64>) -> Result<f64> {
+    let mut parser = Parser {
+        tokens: tokenize(expression)?,
+        position: 0,
+        variables,
+    };
+    let value = parser.sum()?;
+    if let Some(token) = parser.tokens.get(parser.position) {
+        bail!("Unexpected {token:?} in expression `{expression}`");
+    }
+    Ok(value)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(f64),
+    Identifier(String),
+    Operator(char),
+}
+
+fn tokenize(expression: &str) -> Result<Vec<Token>> {
+    let mut tokens = vec![];
+    let mut chars = expression.chars().peekable();
+    while let Some(character) = chars.next() {
+        if character.is_whitespace() {
+            continue;
+        }
+        if character.is_ascii_digit() || character == '.' {
+            let mut number = String::from(character);
+            while let Some(next) = chars.next_if(|next| next.is_ascii_digit() || *next == '.') {
+                number.push(next);
+            }
+            match number.parse() {
+                Ok(number) => tokens.push(Token::Number(number)),
+                Err(_) => bail!("Invalid number `{number}` in expression `{expression}`"),
+            }
+        } else if character.is_alphabetic() || character == '_' {
+            let mut identifier = String::from(character);
+            while let Some(next) = chars.next_if(|next| next.is_alphanumeric() || *next == '_') {
+                identifier.push(next);
+            }
+            tokens.push(Token::Identifier(identifier));
+        } else if "+-*/%^(),".contains(character) {
+            tokens.push(Token::Operator(character));
+        } else {
+            bail!("Unexpected `{character}` in expression `{expression}`");
+        }
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent parser evaluating while parsing.
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    position: usize,
+    variables: &'a BTreeMap<String, f64>,
+}
+
+impl Parser<'_> {
+    /// Consumes the next token if it is the given operator.
+    fn eat(&mut self, operator: char) -> bool {
+        let found = self.tokens.get(self.position) == Some(&Token::Operator(operator));
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn sum(&mut self) -> Result<f64> {
+        let mut value = self.product()?;
+        loop {
+            if self.eat('+') {
+                value += self.product()?;
+            } else if self.eat('-') {
+                value -= self.product()?;
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn product(&mut self) -> Result<f64> {
+        let mut value = self.unary()?;
+        loop {
+            if self.eat('*') {
+                value *= self.unary()?;
+            } else if self.eat('/') {
+                value /= self.unary()?;
+            } else if self.eat('%') {
+                value %= self.unary()?;
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    /// Negation binds weaker than `^`, so `-2^2` is `-4`.
+    fn unary(&mut self) -> Result<f64> {
+        if self.eat('-') {
+            return Ok(-self.unary()?);
+        }
+        self.power()
+    }
+
+    fn power(&mut self) -> Result<f64> {
+        let base = self.atom()?;
+        if self.eat('^') {
+            // Right associative, the exponent may be negated
+            return Ok(base.powf(self.unary()?));
+        }
+        Ok(base)
+    }
+
+    fn atom(&mut self) -> Result<f64> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        match token {
+            Some(Token::Number(number)) => Ok(number),
+            Some(Token::Operator('(')) => {
+                let value = self.sum()?;
+                if !self.eat(')') {
+                    bail!("Missing `)`");
+                }
+                Ok(value)
+            }
+            Some(Token::Identifier(name)) if self.eat('(') => {
+                let mut arguments = vec![self.sum()?];
+                while self.eat(',') {
+                    arguments.push(self.sum()?);
+                }
+                if !self.eat(')') {
+                    bail!("Missing `)` after the arguments of `{name}`");
+                }
+                call(&name, &arguments)
+            }
+            Some(Token::Identifier(name)) => match self.variables.get(&name) {
+                Some(value) => Ok(*value),
+                None => bail!("Unknown variable `{name}`"),
+            },
+            Some(token) => bail!("Unexpected {token:?}"),
+            None => bail!("Unexpected end of expression"),
+        }
+    }
+}
+
+fn call(name: &str, arguments: &[f64]) -> Result<f64> {
+    match (name, arguments) {
+        ("floor", [x]) => Ok(x.floor()),
+        ("ceil", [x]) => Ok(x.ceil()),
+        ("round", [x]) => Ok(x.round()),
+        ("sqrt", [x]) => Ok(x.sqrt()),
+        ("ln", [x]) => Ok(x.ln()),
+        ("log2", [x]) => Ok(x.log2()),
+        ("min", [x, y]) => Ok(x.min(*y)),
+        ("max", [x, y]) => Ok(x.max(*y)),
+        _ => bail!(
+            "Unknown function `{name}` with {} arguments",
+            arguments.len()
+        ),
+    }
+}
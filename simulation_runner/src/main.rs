@@ -1,8 +1,5 @@
-use std::fs::{create_dir_all, read_to_string, File};
-use std::io::Write;
-use std::path::Path;
-use std::sync::{Arc, Mutex};
-use std::thread;
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
 use std::time::Instant;
 
 use anyhow::Result;
@@ -11,12 +8,20 @@ use common::{create_progress_bar, CHECKMARK, FLOPPY_DISK, ROCKET, TOOLS};
 use console::style;
 use indicatif::HumanDuration;
 
-use args::{get_simulation_config, Args};
+use args::{get_simulation_configs, Args};
 use itertools::Itertools;
-use simulation::config::Config;
+use rayon::{prelude::*, ThreadPoolBuilder};
+use scenario::{echo_scenario, load_scenario};
+use simulation::rng::derive_seed;
 use simulation::Simulation;
+use sweep::{echo_sweep, load_sweep};
+use writer::{simulations_path, SimulationWriter};
 
 mod args;
+mod expression;
+mod scenario;
+mod sweep;
+mod writer;
 
 fn main() -> Result<()> {
     let started = Instant::now();
@@ -27,117 +32,100 @@ fn main() -> Result<()> {
     );
     let args = Args::parse();
 
-    // Store finished simulations inside this vector
-    let simulations = Arc::new(Mutex::new(vec![]));
+    // Every configuration derives its seed from this one
+    let (seed, configs) = if let Some(path) = &args.sweep {
+        load_sweep(path)?
+    } else if let Some(path) = &args.scenario {
+        let config = load_scenario(path)?;
+        (config.seed, vec![config])
+    } else {
+        let seed = args.seed.unwrap_or_else(rand::random);
+        (seed, get_simulation_configs(seed, &args)?)
+    };
 
-    let total_n = args.total_n.unwrap_or(args.n);
-    let total_k = args.total_k.unwrap_or(args.k);
-    let total_j = args.total_j.unwrap_or(args.j);
-    let simulation_batch_count = (((total_n - args.n) / args.n_step_size) + 1)
-        * (((total_k - args.k) / args.k_step_size) + 1) as u64
-        * (((total_j - args.j) / args.j_step_size) + 1) as u64;
-    let progress_bar = create_progress_bar(simulation_batch_count)?;
+    // Every run of a configuration starts from a clone of its simulation
+    let templates = configs
+        .iter()
+        .cloned()
+        .map(Simulation::new)
+        .collect::<Result<Vec<_>, _>>()?;
+    let jobs = (0..templates.len())
+        .cartesian_product(0..args.batch_size)
+        .collect_vec();
+
+    // Append finished simulations to this file
+    let writer = Mutex::new(SimulationWriter::open(&args.output)?);
+    // Stored before the run, so an aborted run can be repeated
+    if args.sweep.is_some() {
+        echo_sweep(&args.output, seed, &configs)?;
+    } else if args.scenario.is_some() {
+        echo_scenario(&args.output, &configs[0])?;
+    }
+    let progress_bar = create_progress_bar(jobs.len() as u64)?;
 
-    // Run all possible combinations for supplied n, k and j
+    // Run all configurations
     println!(
-        "{} {} Run simulations...",
+        "{} {} Run simulations with seed {} and write them to {}...",
         style("[2/4]").bold().dim(),
-        ROCKET
+        ROCKET,
+        style(seed).bold(),
+        style(simulations_path(&args.output)).bold()
     );
-    let mut n = args.n;
-    while n <= total_n {
-        let mut k = args.k;
-        while k <= total_k {
-            let mut j = args.j;
-            while j <= total_j {
-                let config =
-                    get_simulation_config(n, j, k, &args.initial_config, args.model.clone())?;
-                progress_bar.set_message(format!("n={n}, k={k}, j={j}"));
-                run_simulations(config, &simulations, args.batch_size)?;
+    // Zero threads lets rayon use all cores
+    let pool = ThreadPoolBuilder::new()
+        .num_threads(args.jobs.map_or(0, NonZeroUsize::get))
+        .build()?;
+    pool.install(|| {
+        jobs.into_par_iter()
+            .try_for_each(|(config_index, replicate)| -> Result<()> {
+                let simulation = &templates[config_index];
+                progress_bar.set_message(format!(
+                    "n={}, k={}, j={}, model={}, tie-break={}",
+                    simulation.n,
+                    simulation.k,
+                    simulation.j,
+                    simulation.model,
+                    simulation.tie_break
+                ));
+                run_simulation(simulation, replicate, args.replay, &writer)?;
                 progress_bar.inc(1);
-                j += args.j_step_size;
-            }
-            k += args.k_step_size;
-        }
-        n += args.n_step_size;
-    }
+                Ok(())
+            })
+    })?;
 
     println!(
-        "{} {} Export data to {}...",
+        "{} {} Sync data to disk...",
         style("[3/4]").bold().dim(),
-        FLOPPY_DISK,
-        style(format!("output/{}/simulation.json", args.output)).bold()
+        FLOPPY_DISK
     );
-    export_simulations(&args, &simulations)?;
+    writer.into_inner().unwrap().sync()?;
 
     println!(
         "{} {} Ran {} simulations in {}",
         style("[4/4]").bold().dim(),
         CHECKMARK,
-        simulation_batch_count * args.batch_size as u64,
+        configs.len() * args.batch_size,
         HumanDuration(started.elapsed())
     );
     Ok(())
 }
 
-/// # Run a batch of simulations in parallel
+/// # Run a simulation
 ///
-/// Creates a simulation config and executes a number of simulations with it
-/// in parallel. Returns after all simulations finished and stores them in the
-/// supplied vector.
-fn run_simulations(
-    config: Config,
-    simulations: &Arc<Mutex<Vec<Simulation>>>,
-    batch_size: usize,
+/// Executes one run of a configuration on a clone of its simulation. Every
+/// run has its own seed derived from the config seed and the replicate index,
+/// unless a seed to replay is supplied. Appends the finished simulation to
+/// the output file.
+fn run_simulation(
+    template: &Simulation,
+    replicate: usize,
+    replay: Option<u64>,
+    writer: &Mutex<SimulationWriter>,
 ) -> Result<()> {
-    let mut handlers = vec![];
-    let simulation = Simulation::new(config)?;
-
-    // Run simulations in multiple threads
-    for _ in 0..batch_size {
-        let mut simulation = simulation.clone();
-        let simulations = Arc::clone(simulations);
-        let handler = thread::spawn(move || {
-            simulation.execute();
-            simulations.lock().unwrap().push(simulation);
-        });
-        handlers.push(handler);
-    }
-
-    // Wait for all simulations to finish
-    handlers.into_iter().for_each(|handler| {
-        handler.join().expect("Simulation thread panicked");
-    });
-
-    Ok(())
-}
-
-/// # Export simulations
-///
-/// Serializes finished simulations and stores them inside a JSON file. If the
-/// file already exists, read previously ran simulations first.
-fn export_simulations(args: &Args, simulations: &Arc<Mutex<Vec<Simulation>>>) -> Result<()> {
-    let mut simulations = simulations.lock().unwrap();
-    let mut all_simulations = vec![];
-    let path = format!("output/{}/simulation.json", args.output);
-
-    // If a file already exists, read previous simulations
-    if Path::new(&path).exists() {
-        all_simulations = serde_json::from_str(&read_to_string(&path)?)?;
-    } else {
-        let dir_path = format!("output/{}", args.output);
-        create_dir_all(dir_path)?;
-    };
-    all_simulations.append(&mut simulations);
-
-    // let averaged_simulations = average_simulations(all_simulations);
-
-    // Create a fresh file and store previous and new simulations in JSON format
-    let mut file = File::create(&path)?;
-    let export = serde_json::to_string_pretty(&all_simulations)?;
-    file.write_all(export.as_bytes())?;
-
-    Ok(())
+    let mut simulation = template.clone();
+    simulation.reseed(replay.unwrap_or_else(|| derive_seed(template.seed, replicate as u64)));
+    simulation.execute();
+    writer.lock().unwrap().write(&simulation)
 }
 
 /// # Average simulations
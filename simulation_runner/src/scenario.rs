@@ -0,0 +1,38 @@
+use std::ffi::OsStr;
+use std::fs::{read_to_string, File};
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use simulation::config::Config;
+
+/// # Load scenario
+///
+/// Reads the config of a scenario file, parsed as JSON for the `.json`
+/// extension and as TOML otherwise, and validates it.
+pub fn load_scenario(path: &str) -> Result<Config> {
+    let content = read_to_string(path).with_context(|| format!("Could not read {path}"))?;
+    let config: Config = match Path::new(path).extension().and_then(OsStr::to_str) {
+        Some("json") => serde_json::from_str(&content)?,
+        _ => toml::from_str(&content)?,
+    };
+    if let Err(error) = config.validate() {
+        let field = error.field();
+        return Err(error).with_context(|| format!("Invalid field `{field}` of scenario {path}"));
+    }
+    Ok(config)
+}
+
+/// # Echo scenario
+///
+/// Stores the resolved scenario, including all defaults and the chosen seed,
+/// as `scenario-<seed>.json` next to the simulations. The file can be passed
+/// to `--scenario` again to rerun the scenario.
+pub fn echo_scenario(output: &str, config: &Config) -> Result<()> {
+    let path = format!("output/{output}/scenario-{}.json", config.seed);
+    let mut file = File::create(path)?;
+    let export = serde_json::to_string_pretty(config)?;
+    file.write_all(export.as_bytes())?;
+
+    Ok(())
+}
@@ -0,0 +1,316 @@
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::fs::{read_to_string, File};
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use simulation::{
+    config::Config,
+    generator::ConfigGenerator,
+    rng::{derive_seed, generator_seed},
+};
+
+use crate::{args::initial_config, expression::evaluate};
+
+/// Grid of simulation configs. Parameters are named like the fields of a
+/// scenario file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Sweep {
+    /// Seed from which the seeds of all configs are derived, chosen randomly
+    /// if not set
+    seed: Option<u64>,
+    /// Parameters shared by all configs
+    #[serde(default)]
+    base: Map<String, Value>,
+    /// Parameters whose values are combined with the values of all other
+    /// parameters
+    #[serde(default)]
+    grid: BTreeMap<String, Values>,
+    /// Groups of parameters whose values are combined position by position,
+    /// each group is combined with all other parameters
+    #[serde(default)]
+    zip: Vec<BTreeMap<String, Values>>,
+    /// Parameters computed from the numeric parameters of each config. Every
+    /// string is an arithmetic expression.
+    #[serde(default)]
+    derived: Map<String, Value>,
+}
+
+/// Values a parameter takes in a sweep.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Values {
+    /// Explicit values
+    List(Vec<Value>),
+    /// `start, start + step, ...` up to `stop`
+    Arithmetic { start: f64, stop: f64, step: f64 },
+    /// `start, start * factor, ...` up to `stop`
+    Geometric { start: f64, stop: f64, factor: f64 },
+    /// `count` values from `start` to `stop`, evenly spaced on a log scale
+    LogSpaced { start: f64, stop: f64, count: usize },
+}
+
+impl Values {
+    /// Returns all values. Ranges with integral bounds yield integers.
+    fn expand(&self) -> Result<Vec<Value>> {
+        // Tolerates rounding errors at the upper bound
+        let within = |value: f64, stop: f64| value <= stop + stop.abs() * 1e-9;
+        let integral = |numbers: &[f64]| numbers.iter().all(|number| number.fract() == 0.0);
+        let mut values = vec![];
+        match *self {
+            Values::List(ref values) => return Ok(values.clone()),
+            Values::Arithmetic { start, stop, step } => {
+                if step <= 0.0 {
+                    bail!("Step of a range has to be positive");
+                }
+                let integral = integral(&[start, step]);
+                let mut index = 0.0;
+                while within(start + index * step, stop) {
+                    values.push(number(start + index * step, integral));
+                    index += 1.0;
+                }
+            }
+            Values::Geometric {
+                start,
+                stop,
+                factor,
+            } => {
+                if start <= 0.0 || factor <= 1.0 {
+                    bail!("Geometric ranges need a positive start and a factor above one");
+                }
+                let integral = integral(&[start, factor]);
+                let mut value = start;
+                while within(value, stop) {
+                    values.push(number(value, integral));
+                    value *= factor;
+                }
+            }
+            Values::LogSpaced { start, stop, count } => {
+                if start <= 0.0 || stop <= 0.0 || count == 0 {
+                    bail!("Log-spaced ranges need positive bounds and count");
+                }
+                let integral = integral(&[start, stop]);
+                for index in 0..count {
+                    let position = index as f64 / (count - 1).max(1) as f64;
+                    let value = (start.ln() + position * (stop.ln() - start.ln())).exp();
+                    values.push(number(value, integral));
+                }
+                // Rounding may map neighboring values to the same integer
+                values.dedup();
+            }
+        }
+        Ok(values)
+    }
+}
+
+/// Converts a number into a value, rounded to an integer if requested or if
+/// it has no fractional part.
+fn number(value: f64, integral: bool) -> Value {
+    if integral || value.fract() == 0.0 {
+        Value::from(value.round() as i64)
+    } else {
+        Value::from(value)
+    }
+}
+
+/// Returns `base` with the parameters of `overrides` replaced. Tables are
+/// merged, so nested parameters can be set one by one.
+fn merge(base: &Map<String, Value>, overrides: &Map<String, Value>) -> Map<String, Value> {
+    let mut merged = base.clone();
+    for (name, value) in overrides {
+        match (merged.get(name), value) {
+            (Some(Value::Object(base)), Value::Object(overrides)) => {
+                let value = Value::Object(merge(base, overrides));
+                merged.insert(name.clone(), value);
+            }
+            _ => {
+                merged.insert(name.clone(), value.clone());
+            }
+        }
+    }
+    merged
+}
+
+/// Evaluates all strings of a derived parameter as expressions.
+fn derive(value: &Value, variables: &BTreeMap<String, f64>) -> Result<Value> {
+    Ok(match value {
+        Value::String(expression) => number(evaluate(expression, variables)?, false),
+        Value::Array(values) => Value::Array(
+            values
+                .iter()
+                .map(|value| derive(value, variables))
+                .collect::<Result<_>>()?,
+        ),
+        Value::Object(parameters) => Value::Object(
+            parameters
+                .iter()
+                .map(|(name, value)| Ok((name.clone(), derive(value, variables)?)))
+                .collect::<Result<_>>()?,
+        ),
+        value => value.clone(),
+    })
+}
+
+impl Sweep {
+    /// Expands the sweep into one config per combination of parameter
+    /// values. The grid parameters, in alphabetical order of their names,
+    /// are followed by the zip groups, and the last of them varies fastest.
+    fn expand(&self, seed: u64) -> Result<Vec<Config>> {
+        // Every dimension lists the assignments of values to parameters
+        let mut dimensions = vec![];
+        for (name, values) in &self.grid {
+            let assignments = values
+                .expand()
+                .with_context(|| format!("Invalid values of {name}"))?
+                .into_iter()
+                .map(|value| Map::from_iter([(name.clone(), value)]))
+                .collect::<Vec<_>>();
+            dimensions.push(assignments);
+        }
+        for group in &self.zip {
+            let mut columns = vec![];
+            for (name, values) in group {
+                let values = values
+                    .expand()
+                    .with_context(|| format!("Invalid values of {name}"))?;
+                columns.push((name, values));
+            }
+            let length = columns.first().map_or(0, |(_, values)| values.len());
+            if columns.iter().any(|(_, values)| values.len() != length) {
+                bail!("All parameters of a zip group need the same number of values");
+            }
+            let assignments = (0..length)
+                .map(|index| {
+                    columns
+                        .iter()
+                        .map(|(name, values)| ((*name).clone(), values[index].clone()))
+                        .collect::<Map<_, _>>()
+                })
+                .collect::<Vec<_>>();
+            dimensions.push(assignments);
+        }
+
+        let mut combinations = vec![self.base.clone()];
+        for assignments in dimensions {
+            combinations = combinations
+                .iter()
+                .flat_map(|combination| {
+                    assignments
+                        .iter()
+                        .map(|assignment| merge(combination, assignment))
+                })
+                .collect();
+        }
+
+        let mut configs = vec![];
+        for (index, combination) in combinations.into_iter().enumerate() {
+            let resolved = self
+                .resolve(
+                    combination,
+                    seed,
+                    configs.len() as u64,
+                    generator_seed(seed, index as u64),
+                )
+                .with_context(|| format!("Invalid combination {index} of the sweep"))?;
+            configs.extend(resolved);
+        }
+        Ok(configs)
+    }
+
+    /// Turns a combination of parameter values into valid configs. A
+    /// generator, seeded with `generator_seed`, may produce several initial
+    /// configurations, the seeds of the configs are derived starting from
+    /// the stream `index`.
+    fn resolve(
+        &self,
+        combination: Map<String, Value>,
+        seed: u64,
+        index: u64,
+        generator_seed: u64,
+    ) -> Result<Vec<Config>> {
+        let variables = combination
+            .iter()
+            .filter_map(|(name, value)| Some((name.clone(), value.as_f64()?)))
+            .collect();
+        let derived = derive(&Value::Object(self.derived.clone()), &variables)?;
+        let Value::Object(derived) = derived else {
+            unreachable!("derived parameters are a table")
+        };
+        let combination = merge(&combination, &derived);
+
+        // Same defaults as on the command line, a missing initial
+        // configuration is left to the deserialization to report
+        let mut initial_configs = vec![None];
+        if !combination.contains_key("config") {
+            let parameter = |name: &str| combination.get(name).and_then(Value::as_u64);
+            let adversary_size = combination
+                .get("adversary")
+                .and_then(|adversary| adversary.get("size"))
+                .and_then(Value::as_u64)
+                .unwrap_or(0);
+            if let (Some(n), Some(k)) = (parameter("n"), parameter("k")) {
+                let honest_agents = n.saturating_sub(adversary_size);
+                let generated = match combination.get("config_generator") {
+                    Some(generator) => serde_json::from_value::<ConfigGenerator>(
+                        generator.clone(),
+                    )?
+                    .generate(honest_agents, k as u16, generator_seed)?,
+                    None => vec![initial_config(&None, honest_agents, k as u16)],
+                };
+                initial_configs = generated.into_iter().map(Some).collect();
+            }
+        }
+
+        let mut configs = vec![];
+        for (offset, initial_config) in initial_configs.into_iter().enumerate() {
+            let mut combination = combination.clone();
+            if let Some(initial_config) = initial_config {
+                combination.insert(String::from("config"), Value::from(initial_config));
+            }
+            combination
+                .entry("seed")
+                .or_insert_with(|| Value::from(derive_seed(seed, index + offset as u64)));
+
+            let config: Config = serde_json::from_value(Value::Object(combination))?;
+            config.validate()?;
+            configs.push(config);
+        }
+        Ok(configs)
+    }
+}
+
+/// # Load sweep
+///
+/// Reads a sweep file, parsed as JSON for the `.json` extension and as TOML
+/// otherwise, and expands it into a list of valid configs.
+/// Returns the seed from which their seeds are derived together with them.
+pub fn load_sweep(path: &str) -> Result<(u64, Vec<Config>)> {
+    let content = read_to_string(path).with_context(|| format!("Could not read {path}"))?;
+    let sweep: Sweep = match Path::new(path).extension().and_then(OsStr::to_str) {
+        Some("json") => serde_json::from_str(&content)?,
+        _ => toml::from_str(&content)?,
+    };
+    let seed = sweep.seed.unwrap_or_else(rand::random);
+    let configs = sweep.expand(seed)?;
+    if configs.is_empty() {
+        bail!("Sweep {path} contains no config");
+    }
+    Ok((seed, configs))
+}
+
+/// # Echo sweep
+///
+/// Stores the expanded configs of a sweep as `sweep-<seed>.json` next to the
+/// simulations.
+pub fn echo_sweep(output: &str, seed: u64, configs: &[Config]) -> Result<()> {
+    let path = format!("output/{output}/sweep-{seed}.json");
+    let mut file = File::create(path)?;
+    let export = serde_json::to_string_pretty(configs)?;
+    file.write_all(export.as_bytes())?;
+
+    Ok(())
+}
@@ -0,0 +1,81 @@
+use std::fs::{create_dir_all, File, OpenOptions};
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use simulation::Simulation;
+
+/// Finished runs written between two syncs to disk at most
+const SYNC_RUNS: usize = 100;
+/// Time between two syncs to disk at most
+const SYNC_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Appends finished simulations to `output/<name>/simulation.ndjson`, one
+/// JSON object per line. Every line is handed to the operating system right
+/// away and synced to disk periodically, so an aborted run keeps all
+/// simulations finished before.
+pub struct SimulationWriter {
+    file: BufWriter<File>,
+    unsynced: usize,
+    last_sync: Instant,
+}
+
+impl SimulationWriter {
+    /// Opens the file of the output folder, creating both if necessary.
+    /// Simulations of previous runs stay in the file.
+    pub fn open(output: &str) -> Result<Self> {
+        create_dir_all(format!("output/{output}"))?;
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(simulations_path(output))?;
+
+        // An aborted run may have left an incomplete last line, which must
+        // not swallow the first new simulation
+        let mut last_byte = [b'\n'];
+        if file.seek(SeekFrom::End(0))? > 0 {
+            file.seek(SeekFrom::End(-1))?;
+            file.read_exact(&mut last_byte)?;
+        }
+        let mut file = BufWriter::new(file);
+        if last_byte[0] != b'\n' {
+            file.write_all(b"\n")?;
+        }
+
+        Ok(SimulationWriter {
+            file,
+            unsynced: 0,
+            last_sync: Instant::now(),
+        })
+    }
+
+    /// Appends a finished simulation.
+    pub fn write(&mut self, simulation: &Simulation) -> Result<()> {
+        serde_json::to_writer(&mut self.file, simulation)?;
+        self.file.write_all(b"\n")?;
+        self.file.flush()?;
+
+        self.unsynced += 1;
+        if self.unsynced >= SYNC_RUNS || self.last_sync.elapsed() >= SYNC_INTERVAL {
+            self.sync()?;
+        }
+        Ok(())
+    }
+
+    /// Writes all appended simulations to disk.
+    pub fn sync(&mut self) -> Result<()> {
+        self.file.flush()?;
+        self.file.get_ref().sync_data()?;
+        self.unsynced = 0;
+        self.last_sync = Instant::now();
+        Ok(())
+    }
+}
+
+/// # Simulations path
+///
+/// Returns the path of the file storing the simulations of an output folder.
+pub fn simulations_path(output: &str) -> String {
+    format!("output/{output}/simulation.ndjson")
+}